@@ -0,0 +1,54 @@
+//! Rough benchmarks of the read path
+//!
+//! Run with `cargo bench`.  Each benchmark reports the mean time per
+//! iteration.
+
+use fileslice::FileSlice;
+use std::io::{Read, Seek, SeekFrom};
+use std::time::{Duration, Instant};
+
+const FILE_LEN: u64 = 16 << 20;
+
+fn bench(name: &str, iters: u32, mut f: impl FnMut()) -> Duration {
+    // Warm up
+    for _ in 0..iters.div_ceil(10) {
+        f();
+    }
+    let start = Instant::now();
+    for _ in 0..iters {
+        f();
+    }
+    let per_iter = start.elapsed() / iters;
+    println!("{name:<24} {per_iter:>12?}/iter");
+    per_iter
+}
+
+fn main() -> std::io::Result<()> {
+    let path = std::env::temp_dir().join(format!("fileslice-bench-{}", std::process::id()));
+    std::fs::write(&path, vec![0xa5; FILE_LEN as usize])?;
+    let slice = FileSlice::new(std::fs::File::open(&path)?);
+
+    let mut buf = vec![0; 64 * 1024];
+    let per_iter = bench("sequential read (64K)", 20, || {
+        let mut s = slice.clone_at_start();
+        while s.read(&mut buf).unwrap() != 0 {}
+    });
+    let throughput = (FILE_LEN >> 20) as f64 / per_iter.as_secs_f64();
+    println!("{:<24} {throughput:>9.0} MiB/s", "");
+
+    let mut s = slice.clone();
+    let mut small = [0; 64];
+    let mut pos = 0u64;
+    bench("random read (64B)", 100_000, || {
+        // A cheap LCG gives us scattered offsets
+        pos = (pos.wrapping_mul(6364136223846793005).wrapping_add(1)) % (FILE_LEN - 64);
+        s.seek(SeekFrom::Start(pos)).unwrap();
+        s.read_exact(&mut small).unwrap();
+    });
+
+    bench("clone", 1_000_000, || {
+        std::hint::black_box(slice.clone());
+    });
+
+    std::fs::remove_file(&path)
+}
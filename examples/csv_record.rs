@@ -0,0 +1,16 @@
+use fileslice::FileSlice;
+use std::io::Read;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let usage = "Usage: csv_record <file> <n>";
+    let path = std::env::args().nth(1).ok_or(usage)?;
+    let n: usize = std::env::args().nth(2).ok_or(usage)?.parse()?;
+    let file = FileSlice::new(std::fs::File::open(path)?);
+    let offsets = file.csv_record_offsets(b',', b'"')?;
+    let start = *offsets.get(n).ok_or("No such record")?;
+    let end = offsets.get(n + 1).copied().unwrap_or(file.len() as u64);
+    let mut record = String::new();
+    file.slice(start..end).read_to_string(&mut record)?;
+    print!("{}", record);
+    Ok(())
+}
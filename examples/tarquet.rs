@@ -1,4 +1,4 @@
-use fileslice::slice_tarball;
+use fileslice::tar_entry;
 use parquet::file::reader::FileReader;
 use parquet::file::serialized_reader::SerializedFileReader;
 use std::path::PathBuf;
@@ -9,13 +9,13 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let table = PathBuf::from(std::env::args().nth(2).ok_or(usage)?);
     let file = std::fs::File::open(path)?;
     let archive = tar::Archive::new(file);
-    for (header, slice) in slice_tarball(archive)? {
-        if header.path()? == table {
-            let rdr = SerializedFileReader::new(slice).unwrap();
-            for row in rdr.get_row_iter(None).unwrap() {
-                println!("{:?}", row);
-            }
-        }
+    let (_, slice) = tar_entry(archive, &table)?.ok_or("No such table")?;
+    if !slice.check_magic(b"PAR1")? {
+        return Err("Not a parquet file".into());
+    }
+    let rdr = SerializedFileReader::new(slice).unwrap();
+    for row in rdr.get_row_iter(None).unwrap() {
+        println!("{:?}", row);
     }
     Ok(())
 }
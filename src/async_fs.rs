@@ -0,0 +1,185 @@
+use super::*;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{mpsc, Mutex, OnceLock};
+use std::task::{Context, Poll, Waker};
+
+impl FileSlice {
+    /// Read up to `len` bytes, starting `offset` bytes into the slice
+    ///
+    /// The read is performed on a background thread (one of a small pool
+    /// shared by all slices), so this won't block the executor.  It doesn't
+    /// depend on any particular runtime, so it works equally well under
+    /// `smol`, `async-std`, or `tokio`.  There are no `futures_io` trait
+    /// impls, and no dependency on `smol` or `blocking`.
+    ///
+    /// The bounds are the same as for the blocking API: the read stops at the
+    /// end of the slice, and reading from beyond the end returns an empty
+    /// buffer.  The cursor is not used or modified.
+    pub async fn read_at_async(&self, offset: u64, len: usize) -> std::io::Result<Vec<u8>> {
+        let mut slice = self.slice(offset..offset.saturating_add(len as u64));
+        unblock(move || {
+            let mut buf = Vec::new();
+            slice.read_to_end(&mut buf)?;
+            Ok(buf)
+        })
+        .await
+    }
+}
+
+/// The number of threads used for running blocking reads
+const WORKERS: usize = 4;
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Run `f` on a background thread, returning a future which resolves to its
+/// output
+///
+/// If `f` panics, the panic is resumed when the future is polled.
+fn unblock<T: Send + 'static>(f: impl FnOnce() -> T + Send + 'static) -> Unblock<T> {
+    static JOBS: OnceLock<mpsc::Sender<Job>> = OnceLock::new();
+    let state = Arc::new(Mutex::new(UnblockState {
+        output: None,
+        waker: None,
+    }));
+    let state2 = state.clone();
+    let job = Box::new(move || {
+        let output = std::panic::catch_unwind(std::panic::AssertUnwindSafe(f));
+        let mut state = state2.lock().unwrap_or_else(|e| e.into_inner());
+        state.output = Some(output);
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+    });
+    let jobs = JOBS.get_or_init(|| {
+        let (tx, rx) = mpsc::channel::<Job>();
+        let rx = Arc::new(Mutex::new(rx));
+        for _ in 0..WORKERS {
+            let rx = rx.clone();
+            std::thread::Builder::new()
+                .name("fileslice-io".into())
+                .spawn(move || loop {
+                    // The guard is dropped at the end of this statement, so
+                    // the lock is only held while waiting for a job, not
+                    // while running it
+                    let job = match rx.lock().unwrap().recv() {
+                        Ok(job) => job,
+                        Err(_) => break,
+                    };
+                    job();
+                })
+                .unwrap();
+        }
+        tx
+    });
+    // The workers never exit, so the channel is never closed
+    jobs.send(job).unwrap();
+    Unblock { state }
+}
+
+struct Unblock<T> {
+    state: Arc<Mutex<UnblockState<T>>>,
+}
+
+struct UnblockState<T> {
+    output: Option<std::thread::Result<T>>,
+    waker: Option<Waker>,
+}
+
+impl<T> Future for Unblock<T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<T> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        match state.output.take() {
+            Some(Ok(x)) => Poll::Ready(x),
+            Some(Err(panic)) => {
+                drop(state);
+                std::panic::resume_unwind(panic)
+            }
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::*;
+    use std::task::Wake;
+
+    /// Drive a future to completion on the current thread
+    fn block_on<F: Future>(fut: F) -> F::Output {
+        struct Unparker(std::thread::Thread);
+        impl Wake for Unparker {
+            fn wake(self: Arc<Self>) {
+                self.0.unpark();
+            }
+        }
+        let waker = Waker::from(Arc::new(Unparker(std::thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        let mut fut = std::pin::pin!(fut);
+        loop {
+            match fut.as_mut().poll(&mut cx) {
+                Poll::Ready(x) => return x,
+                Poll::Pending => std::thread::park(),
+            }
+        }
+    }
+
+    #[test]
+    fn read_whole_slice() {
+        let tmp = fixture(100);
+        let slice = tmp.slice().slice(10..90);
+        let data = block_on(slice.read_at_async(0, 1000)).unwrap();
+        assert_eq!(data, &fixture_bytes(100)[10..90]);
+        let data = block_on(slice.read_at_async(70, 5)).unwrap();
+        assert_eq!(data, &fixture_bytes(100)[80..85]);
+    }
+
+    #[test]
+    fn read_past_end() {
+        let tmp = fixture(100);
+        let slice = tmp.slice().slice(10..90);
+        assert_eq!(block_on(slice.read_at_async(75, 10)).unwrap().len(), 5);
+        assert!(block_on(slice.read_at_async(80, 10)).unwrap().is_empty());
+        assert!(block_on(slice.read_at_async(u64::MAX, 10))
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn many_concurrent_reads() {
+        let tmp = fixture(1000);
+        let slice = tmp.slice();
+        let futs = (0..100)
+            .map(|i| slice.read_at_async(i * 10, 10))
+            .collect::<Vec<_>>();
+        for (i, fut) in futs.into_iter().enumerate() {
+            assert_eq!(block_on(fut).unwrap(), &fixture_bytes(1000)[i * 10..][..10]);
+        }
+    }
+
+    #[test]
+    fn panic_is_resumed() {
+        let fut = unblock(|| -> () { panic!("oh no") });
+        let panic = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| block_on(fut)));
+        assert_eq!(*panic.unwrap_err().downcast::<&str>().unwrap(), "oh no");
+        // The worker survives
+        assert_eq!(block_on(unblock(|| 3)), 3);
+    }
+
+    #[test]
+    fn jobs_run_concurrently() {
+        // The first job can only finish once the second one has run, so
+        // this times out if the workers run one job at a time
+        let (tx, rx) = mpsc::channel();
+        let waiter = unblock(move || rx.recv_timeout(std::time::Duration::from_secs(10)));
+        let releaser = unblock(move || tx.send(()).unwrap());
+        block_on(releaser);
+        assert_eq!(block_on(waiter), Ok(()));
+    }
+}
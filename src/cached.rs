@@ -0,0 +1,246 @@
+use super::*;
+
+impl FileSlice {
+    /// Wrap the slice in a small block cache
+    ///
+    /// See [`CachedFileSlice`] for details.
+    pub fn cached(self, block: usize) -> CachedFileSlice {
+        assert!(block > 0, "block size must be non-zero");
+        CachedFileSlice {
+            inner: self,
+            block: block as u64,
+            buf: vec![0; block],
+            buf_start: 0,
+            buf_len: 0,
+        }
+    }
+}
+
+/// A `FileSlice` which caches the most recently read block
+///
+/// Each read which misses the cache loads the surrounding `block`-aligned
+/// block of the file into memory; subsequent reads which fall within that
+/// block are then served without a syscall.  This helps with access patterns
+/// which make many small reads clustered close together, such as parquet
+/// decoding.  Reads which are at least as large as a block bypass the cache.
+///
+/// Unlike `BufReader`, seeking doesn't throw away the cache.  It implements
+/// `BufRead` too, and [`CachedFileSlice::peek_exact`] lets you look ahead by a
+/// fixed amount.
+#[derive(Debug)]
+pub struct CachedFileSlice {
+    inner: FileSlice,
+    block: u64,
+    buf: Vec<u8>,
+    /// The absolute file offset of the start of `buf`
+    buf_start: u64,
+    buf_len: usize,
+}
+
+impl CachedFileSlice {
+    /// Get back the underlying `FileSlice`, with the cursor where this one
+    /// left it
+    pub fn into_inner(self) -> FileSlice {
+        self.inner
+    }
+
+    /// Look at the next `n` bytes, without moving the cursor
+    ///
+    /// The bytes are borrowed straight from the cache, so this is handy for
+    /// inspecting a header before deciding what to do with it.  If `n` is
+    /// bigger than the block size, the cache grows to fit.  Returns an
+    /// `UnexpectedEof` error if there are fewer than `n` bytes left in the
+    /// slice (including when the cursor is beyond the end, unless `n` is
+    /// zero).  Use `BufRead::consume` to move past the bytes afterwards.
+    pub fn peek_exact(&mut self, n: usize) -> std::io::Result<&[u8]> {
+        let cursor = self.inner.cursor;
+        if n == 0 {
+            // The cursor may be beyond the end, where there's nothing to load
+            return Ok(&[]);
+        }
+        let eof = || std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "Slice is too short");
+        if n as u64 > self.inner.end.saturating_sub(cursor) {
+            return Err(eof());
+        }
+        let buf_end = self.buf_start + self.buf_len as u64;
+        if cursor < self.buf_start || cursor + n as u64 > buf_end {
+            let end = (cursor + (n as u64).max(self.block)).min(self.inner.end);
+            self.load(cursor, end)?;
+            if self.buf_len < n {
+                // The file must have been truncated
+                return Err(eof());
+            }
+        }
+        let offset = (cursor - self.buf_start) as usize;
+        Ok(&self.buf[offset..offset + n])
+    }
+
+    /// Make sure that the byte at the cursor is in the cache, if possible
+    fn fill(&mut self) -> std::io::Result<()> {
+        let cursor = self.inner.cursor;
+        let buf_end = self.buf_start + self.buf_len as u64;
+        if (self.buf_start..buf_end).contains(&cursor) {
+            return Ok(());
+        }
+        let start = (cursor - cursor % self.block).max(self.inner.start);
+        let end = (cursor - cursor % self.block + self.block).min(self.inner.end);
+        self.load(start, end)
+    }
+
+    /// Replace the contents of the cache with the given absolute range of the
+    /// file, growing it if necessary
+    fn load(&mut self, start: u64, end: u64) -> std::io::Result<()> {
+        let len = end.saturating_sub(start) as usize;
+        if self.buf.len() < len {
+            self.buf.resize(len, 0);
+        }
+        self.buf_start = start;
+        self.buf_len = 0;
+        while self.buf_len < len {
+            let pos = start + self.buf_len as u64;
+            match self.inner.pread(&mut self.buf[self.buf_len..len], pos) {
+                Ok(0) => break,
+                Ok(n) => self.buf_len += n,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => (),
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Read for CachedFileSlice {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let cursor = self.inner.cursor;
+        if cursor >= self.inner.end {
+            return Ok(0);
+        }
+        if buf.len() as u64 >= self.block {
+            return self.inner.read(buf);
+        }
+        self.fill()?;
+        let offset = (cursor - self.buf_start) as usize;
+        let cached = self.buf.get(offset..self.buf_len).unwrap_or(&[]);
+        let n = buf.len().min(cached.len());
+        buf[..n].copy_from_slice(&cached[..n]);
+        self.inner.cursor += n as u64;
+        Ok(n)
+    }
+}
+
+impl std::io::BufRead for CachedFileSlice {
+    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+        let cursor = self.inner.cursor;
+        if cursor >= self.inner.end {
+            return Ok(&[]);
+        }
+        self.fill()?;
+        let offset = (cursor - self.buf_start) as usize;
+        Ok(self.buf.get(offset..self.buf_len).unwrap_or(&[]))
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.inner.cursor += amt as u64;
+    }
+}
+
+impl Seek for CachedFileSlice {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        self.inner.seek(pos)
+    }
+
+    fn stream_position(&mut self) -> std::io::Result<u64> {
+        self.inner.stream_position()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::*;
+    use std::io::BufRead;
+
+    /// Many small reads, all within the same 4 KiB
+    fn clustered_reads(r: &mut (impl Read + Seek)) -> Vec<u8> {
+        let mut out = vec![];
+        for i in 0..100 {
+            let mut buf = [0; 8];
+            r.seek(SeekFrom::Start(i * 37 % 4000)).unwrap();
+            r.read_exact(&mut buf).unwrap();
+            out.extend_from_slice(&buf);
+        }
+        out
+    }
+
+    #[test]
+    fn clustered_access_syscalls() {
+        let tmp = fixture(16 * 1024);
+        let mut uncached = tmp.slice();
+        let mut expected = vec![];
+        assert_eq!(
+            preads_during(|| expected = clustered_reads(&mut uncached)),
+            100
+        );
+        let mut cached = tmp.slice().cached(4096);
+        let mut got = vec![];
+        assert_eq!(preads_during(|| got = clustered_reads(&mut cached)), 1);
+        assert_eq!(got, expected);
+    }
+
+    #[test]
+    fn cache_is_bounded_by_slice() {
+        let tmp = fixture(16 * 1024);
+        let mut cached = tmp.slice().slice(100..5000).cached(4096);
+        cached.seek(SeekFrom::Start(4890)).unwrap();
+        let mut buf = vec![];
+        cached.read_to_end(&mut buf).unwrap();
+        assert_eq!(buf, &fixture_bytes(16 * 1024)[4990..5000]);
+        cached.seek(SeekFrom::Start(0)).unwrap();
+        let mut buf = [0; 4];
+        cached.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, fixture_bytes(16 * 1024)[100..104]);
+    }
+
+    #[test]
+    fn peek_then_consume() {
+        let tmp = fixture(100);
+        let mut cached = tmp.slice().cached(16);
+        let header = cached.peek_exact(4).unwrap().to_vec();
+        assert_eq!(header, &fixture_bytes(100)[..4]);
+        assert_eq!(cached.peek_exact(4).unwrap(), header);
+        cached.consume(4);
+        let mut buf = [0; 4];
+        cached.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, fixture_bytes(100)[4..8]);
+    }
+
+    #[test]
+    fn peek_grows_buffer() {
+        let tmp = fixture(100);
+        let mut cached = tmp.slice().cached(16);
+        cached.seek(SeekFrom::Start(10)).unwrap();
+        assert_eq!(cached.peek_exact(50).unwrap(), &fixture_bytes(100)[10..60]);
+        assert_eq!(cached.stream_position().unwrap(), 10);
+        assert_eq!(cached.peek_exact(90).unwrap(), &fixture_bytes(100)[10..]);
+        assert_eq!(
+            cached.peek_exact(91).unwrap_err().kind(),
+            std::io::ErrorKind::UnexpectedEof
+        );
+    }
+
+    #[test]
+    fn peek_beyond_end() {
+        let tmp = fixture(100);
+        let mut cached = tmp.slice().slice(10..50).cached(16);
+        cached.seek(SeekFrom::End(10)).unwrap();
+        assert_eq!(cached.peek_exact(0).unwrap(), &[] as &[u8]);
+        let err = cached.peek_exact(1).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+        // Right at the end
+        cached.seek(SeekFrom::End(0)).unwrap();
+        assert_eq!(cached.peek_exact(0).unwrap(), &[] as &[u8]);
+        assert!(cached.peek_exact(1).is_err());
+        cached.seek(SeekFrom::Start(39)).unwrap();
+        assert_eq!(cached.peek_exact(1).unwrap(), &fixture_bytes(100)[49..50]);
+    }
+}
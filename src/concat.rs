@@ -0,0 +1,117 @@
+use super::*;
+
+/// Join several slices into one contiguous stream
+///
+/// The slices may come from different files.  The returned reader behaves
+/// as if the slices had been laid end-to-end: reads which cross a boundary
+/// carry on into the next slice, and seeks are translated into the
+/// appropriate slice.  The total length (as seen by `SeekFrom::End`) is the
+/// sum of the lengths of the slices.
+///
+/// The cursors of the provided slices are ignored.
+pub fn concat(slices: Vec<FileSlice>) -> impl Read + Seek {
+    let mut offsets = Vec::with_capacity(slices.len());
+    let mut len = 0;
+    for slice in &slices {
+        offsets.push(len);
+        len += slice.end - slice.start;
+    }
+    Concat {
+        slices,
+        offsets,
+        len,
+        pos: 0,
+    }
+}
+
+struct Concat {
+    slices: Vec<FileSlice>,
+    /// The offset at which each slice begins, relative to the start of the
+    /// stream
+    offsets: Vec<u64>,
+    len: u64,
+    /// Can go beyond `len`
+    pos: u64,
+}
+
+impl Read for Concat {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.pos >= self.len {
+            return Ok(0);
+        }
+        // Empty slices share their offset with the next one, so this picks
+        // the non-empty slice which contains `pos`
+        let idx = self.offsets.partition_point(|&x| x <= self.pos) - 1;
+        let slice = &self.slices[idx];
+        let offset = self.pos - self.offsets[idx];
+        let remaining = (slice.end - slice.start - offset) as usize;
+        let buf = if buf.len() > remaining {
+            &mut buf[..remaining]
+        } else {
+            buf
+        };
+        let x = slice.pread(buf, slice.start + offset)?;
+        self.pos += x as u64;
+        Ok(x)
+    }
+}
+
+impl Seek for Concat {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let pos = match pos {
+            SeekFrom::Current(x) => i128::from(self.pos) + i128::from(x),
+            SeekFrom::Start(x) => i128::from(x),
+            SeekFrom::End(x) => i128::from(self.len) + i128::from(x),
+        };
+        self.pos = u64::try_from(pos).map_err(|_| std::io::Error::other("Out of bounds"))?;
+        Ok(self.pos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::*;
+
+    #[test]
+    fn read_across_boundaries() {
+        let a = fixture(100);
+        let b = TempFile::new(b"0123456789");
+        let mut joined = concat(vec![
+            a.slice().slice(10..20),
+            b.slice().slice(5..5),
+            b.slice().slice(2..8),
+            a.slice().slice(90..),
+        ]);
+        let mut expected = fixture_bytes(100)[10..20].to_vec();
+        expected.extend_from_slice(b"234567");
+        expected.extend_from_slice(&fixture_bytes(100)[90..]);
+        let mut buf = vec![];
+        joined.read_to_end(&mut buf).unwrap();
+        assert_eq!(buf, expected);
+        assert_eq!(joined.seek(SeekFrom::End(0)).unwrap(), 26);
+
+        // Seek into the middle of the first slice and read across into the
+        // second
+        joined.seek(SeekFrom::Start(7)).unwrap();
+        let mut buf = [0; 6];
+        joined.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, expected[7..13]);
+        assert_eq!(joined.seek(SeekFrom::Current(-1)).unwrap(), 12);
+        let mut buf = [0; 8];
+        joined.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, expected[12..20]);
+    }
+
+    #[test]
+    fn seek_out_of_range() {
+        let a = fixture(100);
+        let mut joined = concat(vec![a.slice().slice(..10), a.slice().slice(20..30)]);
+        assert!(joined.seek(SeekFrom::Current(-1)).is_err());
+        assert_eq!(joined.seek(SeekFrom::End(5)).unwrap(), 25);
+        assert_eq!(joined.read(&mut [0; 4]).unwrap(), 0);
+        let mut empty = concat(vec![]);
+        assert_eq!(empty.read(&mut [0; 4]).unwrap(), 0);
+        assert_eq!(empty.seek(SeekFrom::End(0)).unwrap(), 0);
+    }
+}
@@ -0,0 +1,89 @@
+use super::*;
+
+impl FileSlice {
+    /// Convert the slice into one which grows along with the file
+    ///
+    /// See [`FollowingFileSlice`].
+    pub fn following(self) -> FollowingFileSlice {
+        FollowingFileSlice { inner: self }
+    }
+}
+
+/// A `FileSlice` which follows the end of a growing file, like `tail -f`
+///
+/// When a read reaches the end of the slice, the file's length is queried
+/// again, and if it's grown, the end of the slice is moved forward to match
+/// and the read carries on into the new data.  So `read` only returns `Ok(0)`
+/// if there's nothing new in the file; if you're following a log, you'd poll
+/// again after a short sleep.  The start of the slice doesn't move, and the
+/// end never moves backwards, even if the file is truncated.
+#[derive(Clone, Debug)]
+pub struct FollowingFileSlice {
+    inner: FileSlice,
+}
+
+impl FollowingFileSlice {
+    /// Get back the underlying `FileSlice`, with the bounds and cursor where
+    /// this one left them
+    pub fn into_inner(self) -> FileSlice {
+        self.inner
+    }
+}
+
+impl Read for FollowingFileSlice {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let x = self.inner.read(buf)?;
+        if x > 0 || buf.is_empty() {
+            return Ok(x);
+        }
+        let file_len = self.inner.file.metadata()?.len();
+        if file_len <= self.inner.end {
+            return Ok(0);
+        }
+        self.inner.end = file_len;
+        self.inner.read(buf)
+    }
+}
+
+impl Seek for FollowingFileSlice {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        self.inner.seek(pos)
+    }
+
+    fn stream_position(&mut self) -> std::io::Result<u64> {
+        self.inner.stream_position()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::*;
+
+    #[test]
+    fn reads_appended_data() {
+        let tmp = TempFile::new(b"line 1\n");
+        let mut follower = tmp.slice().following();
+        let mut buf = vec![];
+        follower.read_to_end(&mut buf).unwrap();
+        assert_eq!(buf, b"line 1\n");
+        assert_eq!(follower.read(&mut [0; 16]).unwrap(), 0);
+        tmp.append(b"line 2\n");
+        let mut buf = [0; 16];
+        assert_eq!(follower.read(&mut buf).unwrap(), 7);
+        assert_eq!(&buf[..7], b"line 2\n");
+        assert_eq!(follower.read(&mut buf).unwrap(), 0);
+        assert_eq!(follower.into_inner().end_pos(), 14);
+    }
+
+    #[test]
+    fn end_never_moves_back() {
+        let tmp = fixture(100);
+        let mut follower = tmp.slice().slice(10..).following();
+        follower.seek(SeekFrom::End(0)).unwrap();
+        tmp.set_len(50);
+        assert_eq!(follower.read(&mut [0; 16]).unwrap(), 0);
+        let inner = follower.into_inner();
+        assert_eq!((inner.start_pos(), inner.end_pos()), (10, 100));
+    }
+}
@@ -4,10 +4,12 @@
 `Vec<u8>`.  Advantages over `File`:
 
 * You can slice it, reducing the scope to a range within the original file
-* Cloning is cheap (atomic addition; no syscall)
+* Cloning is cheap (atomic addition, or two with the `mmap` feature; no
+  syscall)
 * Seeking is very cheap (normal addition; no syscall)
-* Clones can't affect each other at all (the fd's real cursor is never
-  used).
+* Clones can't affect each other at all (reads never use the fd's real
+  cursor; only [`FileSlice::data_regions`] moves it, as a side-effect of
+  `lseek`).
 
 Once created, a `FileSlice` never changes length, even if the underlying file
 does.  For example, if another process appends some data to the file, you need
@@ -24,18 +26,37 @@ Optional integrations for crates which naturally benefit from file slicing:
   needs to interleave reads from these pages.  The `ChunkReader` impl for `File`
   accomplishes this by making many clones of the fd.  Using `FileSlice` instead
   lets you open roughly 7x as many parquet files before you hit your fd limit.
+* `async-fs`: Adds [`FileSlice::read_at_async`], which performs reads on a
+  small pool of background threads.  It's runtime-agnostic, so it's suitable
+  for use with `smol` and friends (but there are no `futures_io` trait impls).
+* `zstd`: Adds [`FileSlice::slice_zstd_frames`], for splitting a file in the
+  zstd seekable format into independently-decompressible frames.
+* `mmap`: Adds [`FileSlice::map_region`], for memory-mapping part of a slice,
+  and [`FileSlice::shared_mmap`], for sharing a single mapping between all
+  slices of a file (unix only).
+* `testing`: Adds [`FileSlice::from_vec`], for making a slice out of some
+  bytes in a test.
+* `zerocopy`: Adds [`FileSlice::read_struct`], for reading a
+  [`zerocopy::FromBytes`] type (eg. a `#[repr(C)]` header) from a slice.
 
 */
 
-use std::fs::File;
+use std::fs::{File, OpenOptions};
 use std::io::{Read, Seek, SeekFrom};
-use std::ops::{Bound, RangeBounds};
+use std::ops::{Bound, Range, RangeBounds};
+use std::path::Path;
 use std::sync::Arc;
 
 /// A slice of a file
 ///
 /// Behaves like a regular file, but emulated in userspace using the
 /// `pread` API.
+///
+/// Cloning a `FileSlice` copies its cursor: the clone resumes reading from
+/// wherever the original had got to.  If you want a fresh reader positioned
+/// at the beginning of the slice, use [`FileSlice::clone_reset`] instead.
+/// This matters when handing a slice to a decoder which expects to start
+/// reading from the beginning, after you've already read (say) its header.
 #[derive(Clone, Debug)]
 pub struct FileSlice {
     file: Arc<File>,
@@ -43,48 +64,482 @@ pub struct FileSlice {
     cursor: u64,
     start: u64,
     end: u64,
+    /// A mapping of the whole file, shared by all slices of `file`
+    #[cfg(all(unix, feature = "mmap"))]
+    mmap: Arc<std::sync::OnceLock<Arc<MappedSlice>>>,
 }
 
 impl FileSlice {
     /// Create a new slice covering the whole file
     pub fn new(file: File) -> FileSlice {
         let end = file.metadata().unwrap().len();
+        FileSlice::from_file(Arc::new(file), 0, end, 0)
+    }
+
+    /// Create a new slice covering the given range of the file
+    ///
+    /// This is equivalent to `FileSlice::new(file).slice(range)`.  The range
+    /// is clamped to the length of the file.
+    pub fn new_range<R: RangeBounds<u64>>(file: File, range: R) -> FileSlice {
+        let mut slice = FileSlice::new(file);
+        let (start, end) = slice.resolve_range(range);
+        let start = start.min(slice.end);
+        slice.end = end.min(slice.end).max(start);
+        slice.start = start;
+        slice.cursor = start;
+        slice
+    }
+
+    /// Open the file at `path` with the given options, and create a slice
+    /// covering the given range of it
+    ///
+    /// The range is clamped to the length of the file.
+    pub fn open_with<P, R>(path: P, opts: &OpenOptions, range: R) -> std::io::Result<FileSlice>
+    where
+        P: AsRef<Path>,
+        R: RangeBounds<u64>,
+    {
+        let file = opts.open(path)?;
+        let len = file.metadata()?.len();
+        Ok(FileSlice::with_file_len(file, len).slice(range))
+    }
+
+    /// Create a new slice covering the whole file, whose length is already
+    /// known
+    ///
+    /// This is the same as [`FileSlice::new`], but saves a call to `fstat`.
+    /// The length is trusted: if it's too short, the slice won't cover the
+    /// whole file; if it's too long, reads near the end will come back short.
+    pub fn with_file_len(file: File, len: u64) -> FileSlice {
+        FileSlice::from_file(Arc::new(file), 0, len, 0)
+    }
+
+    /// Resize the file to `len` bytes, and create a slice covering all of it
+    ///
+    /// On Linux, the space is actually reserved on disk (with
+    /// `posix_fallocate`), so that writing into it later won't fail for lack
+    /// of space; if the filesystem doesn't support this, or on other
+    /// platforms, the file is simply resized with `set_len`.  If the file was
+    /// longer than `len`, it's truncated.  The file must have been opened for
+    /// writing.
+    ///
+    /// This is for building a file of known size: you can then `slice` out
+    /// regions of it for separate writers to fill in.
+    pub fn preallocate(file: File, len: u64) -> std::io::Result<FileSlice> {
+        let slice = FileSlice::with_file_len(file, len);
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        slice.allocate(0, len)?;
+        slice.file.set_len(len)?;
+        Ok(slice)
+    }
+
+    /// Reassemble a slice from the parts returned by [`FileSlice::into_parts`]
+    ///
+    /// `start` and `end` are absolute offsets into `file`, and so is `cursor`.
+    /// The cursor may be beyond `end` but not before `start`.  The bounds are
+    /// not checked against the length of the file; see
+    /// [`FileSlice::from_parts_checked`] for that.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `start > end` or `cursor < start`.
+    pub fn from_parts(file: Arc<File>, start: u64, end: u64, cursor: u64) -> FileSlice {
+        assert!(start <= end, "start ({start}) is beyond end ({end})");
+        assert!(
+            cursor >= start,
+            "cursor ({cursor}) is before start ({start})"
+        );
+        FileSlice::from_file(file, start, end, cursor)
+    }
+
+    /// Like [`FileSlice::from_parts`], but checks the parts against the file
+    ///
+    /// This stats the file, and returns an error if `end` is beyond the end of
+    /// it.  Invalid bounds (`start > end` or `cursor < start`) are also
+    /// reported as errors rather than panics.
+    pub fn from_parts_checked(
+        file: Arc<File>,
+        start: u64,
+        end: u64,
+        cursor: u64,
+    ) -> std::io::Result<FileSlice> {
+        let invalid = |msg| std::io::Error::new(std::io::ErrorKind::InvalidInput, msg);
+        if start > end {
+            return Err(invalid("start is beyond end"));
+        }
+        if cursor < start {
+            return Err(invalid("cursor is before start"));
+        }
+        if end > file.metadata()?.len() {
+            return Err(invalid("end is beyond the end of the file"));
+        }
+        Ok(FileSlice::from_file(file, start, end, cursor))
+    }
+
+    /// Build a slice of a `File` which isn't shared with any existing slices
+    fn from_file(file: Arc<File>, start: u64, end: u64, cursor: u64) -> FileSlice {
+        FileSlice {
+            file,
+            cursor,
+            start,
+            end,
+            #[cfg(all(unix, feature = "mmap"))]
+            mmap: Default::default(),
+        }
+    }
+
+    /// Build another slice of the same `File` as `self`
+    fn derive(&self, start: u64, end: u64, cursor: u64) -> FileSlice {
         FileSlice {
-            file: Arc::new(file),
-            cursor: 0,
-            start: 0,
+            file: self.file.clone(),
+            cursor,
+            start,
             end,
+            #[cfg(all(unix, feature = "mmap"))]
+            mmap: self.mmap.clone(),
         }
     }
 
+    /// Clone the slice, with the clone's cursor at the beginning
+    ///
+    /// This is in contrast to `clone()`, which preserves the cursor.
+    pub fn clone_at_start(&self) -> FileSlice {
+        self.derive(self.start, self.end, self.start)
+    }
+
+    /// Clone the slice, with the clone's cursor at the beginning
+    ///
+    /// This is the same as [`FileSlice::clone_at_start`].  Use it wherever a
+    /// fresh reader is wanted, since `clone()` preserves the cursor.
+    pub fn clone_reset(&self) -> FileSlice {
+        self.clone_at_start()
+    }
+
     /// Take a sub-slice of this file
+    ///
+    /// The range is relative to the start of `self`, and is clamped so that
+    /// the sub-slice never extends beyond `self`.  The cursor of the new slice
+    /// is positioned at its beginning, regardless of where the cursor of
+    /// `self` is.
     pub fn slice<T>(&self, range: T) -> FileSlice
     where
         T: RangeBounds<u64>,
     {
         // The parameters are interpreted relative to `self`
+        let (start, end) = self.resolve_range(range);
+        // Not allowed to expand beyond `self`
+        let start = start.min(self.end);
+        let end = end
+            .min(self.end) // Not allowed to expand beyond `self`
+            .max(start); // We require that `start <= end`
+        self.derive(start, end, start)
+    }
+}
+
+impl FileSlice {
+    /// Take several sub-slices at once
+    ///
+    /// Each range is interpreted as for [`FileSlice::slice`].
+    pub fn slice_many<I>(&self, ranges: I) -> Vec<FileSlice>
+    where
+        I: IntoIterator<Item = Range<u64>>,
+    {
+        ranges.into_iter().map(|range| self.slice(range)).collect()
+    }
+
+    /// Iterate over windows of `size` bytes, each starting `step` bytes after
+    /// the previous one
+    ///
+    /// If `step < size`, the windows overlap.  The first window starts at the
+    /// start of the slice, and there's a window starting at every multiple of
+    /// `step` up to the first one which reaches the end of the slice.  That
+    /// last window may be short.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` or `step` is zero.
+    pub fn windows(&self, size: u64, step: u64) -> impl Iterator<Item = FileSlice> {
+        assert!(size > 0, "window size must be non-zero");
+        assert!(step > 0, "step must be non-zero");
+        let slice = self.clone();
+        let mut pos = self.start;
+        std::iter::from_fn(move || {
+            if pos >= slice.end {
+                return None;
+            }
+            let end = pos.saturating_add(size).min(slice.end);
+            let window = slice.derive(pos, end, pos);
+            // Any further windows would lie within this one
+            pos = if end == slice.end {
+                end
+            } else {
+                pos.saturating_add(step)
+            };
+            Some(window)
+        })
+    }
+
+    /// Take a sub-slice of this file, failing if the range is invalid
+    ///
+    /// This is a strict version of [`FileSlice::slice`].  Instead of
+    /// clamping, it returns an error if the range is inverted or extends
+    /// beyond the end of `self`.
+    pub fn checked_slice<R: RangeBounds<u64>>(&self, range: R) -> Result<FileSlice, SliceError> {
+        let (start, end) = self.resolve_range(range);
+        if start > end {
+            return Err(SliceError::Inverted);
+        }
+        if end > self.end {
+            return Err(SliceError::OutOfBounds);
+        }
+        Ok(self.derive(start, end, start))
+    }
+
+    /// Convert a range relative to the start of the slice into absolute
+    /// `(start, end)` offsets, without any clamping
+    fn resolve_range<T>(&self, range: T) -> (u64, u64)
+    where
+        T: RangeBounds<u64>,
+    {
+        // Saturating, so that eg. `..=u64::MAX` means "to the end"
         let start = match range.start_bound() {
-            Bound::Included(x) => self.start + x,
-            Bound::Excluded(x) => self.start + x + 1,
+            Bound::Included(x) => self.start.saturating_add(*x),
+            Bound::Excluded(x) => self.start.saturating_add(*x).saturating_add(1),
             Bound::Unbounded => self.start,
         };
         let end = match range.end_bound() {
-            Bound::Included(x) => self.start + x + 1,
-            Bound::Excluded(x) => self.start + x,
+            Bound::Included(x) => self.start.saturating_add(*x).saturating_add(1),
+            Bound::Excluded(x) => self.start.saturating_add(*x),
             Bound::Unbounded => self.end,
         };
-        let end = end
-            .min(self.end) // Not allowed to expand beyond `self`
-            .max(start); // We require that `start <= end`
-        FileSlice {
-            file: self.file.clone(),
-            cursor: start,
-            start,
-            end,
+        (start, end)
+    }
+
+    /// Split the slice in two at an offset relative to its start
+    ///
+    /// The first half covers `..mid` and the second covers `mid..`.  If `mid`
+    /// is beyond the end of the slice, it's clamped (so the second half is
+    /// empty).  Both halves have their cursors at their beginnings.
+    pub fn split_at(&self, mid: u64) -> (FileSlice, FileSlice) {
+        let mid = mid.min(self.end - self.start);
+        (self.slice(..mid), self.slice(mid..))
+    }
+
+    /// Split the slice into contiguous pieces at the given offsets
+    ///
+    /// The offsets are relative to the start of the slice.  They're clamped to
+    /// the length of the slice, sorted, and deduplicated, and then the slice
+    /// is cut at each one; so with `n` distinct cuts you get `n + 1` pieces,
+    /// which together cover the whole slice.  A cut at `0` or at the end
+    /// produces an empty piece.  Each piece has its cursor at its beginning.
+    pub fn split_at_offsets(&self, cuts: &[u64]) -> Vec<FileSlice> {
+        let len = self.end - self.start;
+        let mut cuts: Vec<u64> = cuts.iter().map(|&x| x.min(len)).collect();
+        cuts.sort_unstable();
+        cuts.dedup();
+        let mut pieces = Vec::with_capacity(cuts.len() + 1);
+        let mut prev = 0;
+        for cut in cuts.into_iter().chain(std::iter::once(len)) {
+            pieces.push(self.slice(prev..cut));
+            prev = cut;
+        }
+        pieces
+    }
+
+    /// The part of the slice which hasn't been read yet
+    ///
+    /// Returns a new slice covering the range from the cursor to the end, with
+    /// its cursor at its beginning.  If the cursor is beyond the end, the
+    /// returned slice is empty.
+    pub fn remaining_slice(&self) -> FileSlice {
+        self.slice((self.cursor.min(self.end) - self.start)..)
+    }
+
+    /// The next `n` bytes after the cursor, as a new slice
+    ///
+    /// This is similar to `Read::take`, but the result is a `FileSlice`, so
+    /// it's still seekable and knows its own length; prefer it over `take`.
+    /// The new slice is clamped to the end of `self`, and its cursor is at its
+    /// beginning.  The cursor of `self` is not modified.
+    pub fn limited(&self, n: u64) -> FileSlice {
+        let start = self.cursor.min(self.end) - self.start;
+        self.slice(start..start.saturating_add(n))
+    }
+
+    /// Expand the slice outwards so that its bounds are multiples of `align`
+    ///
+    /// The start is rounded down and the end is rounded up (but not beyond the
+    /// end of the underlying file).  This is useful for `O_DIRECT` reads and
+    /// memory-mapping, which have alignment requirements.  If the slice is
+    /// already aligned it's returned unchanged.  The cursor is preserved.
+    /// Returns an error if the length of the file can't be queried.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `align` is zero.
+    pub fn aligned_slice(&self, align: u64) -> std::io::Result<FileSlice> {
+        assert!(align > 0, "alignment must be non-zero");
+        if self.start.is_multiple_of(align) && self.end.is_multiple_of(align) {
+            return Ok(self.clone());
+        }
+        let file_len = self.file.metadata()?.len();
+        let start = self.start - self.start % align;
+        let end = self
+            .end
+            .checked_next_multiple_of(align)
+            .unwrap_or(u64::MAX)
+            .min(file_len.max(self.end));
+        Ok(self.derive(start, end, self.cursor))
+    }
+
+    /// Shrink the slice so that it ends at the cursor
+    ///
+    /// Afterwards the slice covers just the part which has already been read.
+    /// This is the complement of [`FileSlice::remaining_slice`].  If the
+    /// cursor is beyond the end, the slice is unchanged.
+    pub fn truncate_to_cursor(&mut self) {
+        self.end = self.cursor.min(self.end);
+    }
+
+    /// Drop the first `n` bytes of the slice
+    ///
+    /// This is handy for skipping a header of known size.  If the slice is
+    /// shorter than `n` bytes, it ends up empty.  If the cursor was in the
+    /// dropped part, it moves forward to the new start.
+    pub fn shrink_start(&mut self, n: u64) {
+        self.start = self.start.saturating_add(n).min(self.end);
+        self.cursor = self.cursor.max(self.start);
+    }
+
+    /// Drop the last `n` bytes of the slice
+    ///
+    /// This is handy for skipping a footer of known size.  If the slice is
+    /// shorter than `n` bytes, it ends up empty.  If the cursor was beyond the
+    /// new end, it moves back to it.
+    pub fn shrink_end(&mut self, n: u64) {
+        self.end = self.end.saturating_sub(n).max(self.start);
+        self.cursor = self.cursor.min(self.end);
+    }
+
+    /// The next `len` bytes after the cursor, as a new slice
+    ///
+    /// This is an alias for [`FileSlice::limited`], named for the common case
+    /// of reading a header which gives the length of a body which follows
+    /// it.
+    pub fn slice_from_cursor(&self, len: u64) -> FileSlice {
+        self.limited(len)
+    }
+
+    /// Like [`FileSlice::split_at`], but returns `None` if `mid > len()`
+    pub fn split_at_checked(&self, mid: u64) -> Option<(FileSlice, FileSlice)> {
+        if mid > self.end - self.start {
+            return None;
+        }
+        Some(self.split_at(mid))
+    }
+}
+
+/// The region covered by a slice, as `(start, end)` offsets into the
+/// underlying file
+///
+/// This is returned by [`FileSlice::key`], and is handy for keying maps by
+/// region.  Keys are ordered by start and then by end.  Note that a key
+/// doesn't identify the file, so keys of slices from different files may
+/// collide; keeping them apart is up to you.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SliceKey(pub u64, pub u64);
+
+/// Identifies a file on disk
+///
+/// This is returned by [`FileSlice::file_identity`].  Unlike
+/// [`FileSlice::is_same_file`], it identifies the file itself rather than the
+/// open `File`, so two slices of the same file have the same `FileId` even if
+/// it was opened separately for each of them.  On unix it's made from the
+/// device and inode numbers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FileId {
+    dev: u64,
+    ino: u64,
+}
+
+impl FileId {
+    fn from_metadata(meta: &std::fs::Metadata) -> std::io::Result<FileId> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            Ok(FileId {
+                dev: meta.dev(),
+                ino: meta.ino(),
+            })
+        }
+        #[cfg(not(unix))]
+        {
+            let _ = meta;
+            Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "File identities are only supported on unix",
+            ))
+        }
+    }
+}
+
+/// A hint about how part of a file is going to be accessed
+///
+/// See [`FileSlice::advise_range`].  These correspond to the `POSIX_FADV_*`
+/// constants.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Advice {
+    /// No particular pattern (the default)
+    Normal,
+    /// The data will be read from start to end
+    Sequential,
+    /// The data will be read in a random order
+    Random,
+    /// The data will be read soon
+    WillNeed,
+    /// The data won't be read again any time soon
+    DontNeed,
+    /// The data will only be read once
+    NoReuse,
+}
+
+/// The error returned by [`FileSlice::checked_slice`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SliceError {
+    /// The start of the range is after the end
+    Inverted,
+    /// The range extends beyond the end of the parent slice
+    OutOfBounds,
+}
+
+impl std::fmt::Display for SliceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            SliceError::Inverted => f.write_str("The start of the range is after the end"),
+            SliceError::OutOfBounds => f.write_str("The range extends beyond the end of the slice"),
         }
     }
 }
 
+impl std::error::Error for SliceError {}
+
+/// A summary of the region covered by the slice, like
+/// `[0x1000..0x2000] (4096 bytes, cursor @ 0x1040)`
+///
+/// The offsets are absolute positions in the underlying file.
+impl std::fmt::Display for FileSlice {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "[{:#x}..{:#x}] ({} bytes, cursor @ {:#x})",
+            self.start,
+            self.end,
+            self.end - self.start,
+            self.cursor,
+        )
+    }
+}
+
 impl FileSlice {
     /// The position at which this slice begins, as a byte offset into the
     /// underlying file
@@ -99,10 +554,65 @@ impl FileSlice {
     }
 
     /// The next byte to be read, as an offset into the underlying file
+    ///
+    /// See also [`FileSlice::cursor_relative`], which is relative to the
+    /// start of the slice.
     pub fn cursor_pos(&self) -> u64 {
         self.cursor
     }
 
+    /// The next byte to be read, as an offset from the start of the slice
+    ///
+    /// This is the same as `stream_position()`, but doesn't need `&mut self`.
+    pub fn cursor_relative(&self) -> u64 {
+        self.cursor - self.start
+    }
+
+    /// The position of the cursor relative to the start of the slice,
+    /// clamped to `len()`
+    ///
+    /// This is the same as `stream_position()`, except when the cursor has
+    /// been moved beyond the end of the slice.
+    pub fn position_clamped(&self) -> u64 {
+        (self.cursor - self.start).min(self.end - self.start)
+    }
+
+    /// The region covered by this slice, for use as a map key
+    pub fn key(&self) -> SliceKey {
+        SliceKey(self.start, self.end)
+    }
+
+    /// Whether the two slices share the same open file
+    ///
+    /// This is true for slices derived (by cloning, slicing, etc.) from the
+    /// same original `FileSlice`.  Two `FileSlice`s created by opening the
+    /// same path separately are _not_ considered the same file.  This doesn't
+    /// involve a syscall.
+    pub fn is_same_file(&self, other: &FileSlice) -> bool {
+        Arc::ptr_eq(&self.file, &other.file)
+    }
+
+    /// How far `other` starts after `self`, if they're slices of the same file
+    ///
+    /// Returns `other.start_pos() - self.start_pos()`, which is negative if
+    /// `other` starts first.  Returns `None` if the two slices don't share the
+    /// same open file (see [`FileSlice::is_same_file`]), or if the distance
+    /// doesn't fit in an `i64`.
+    pub fn offset_of(&self, other: &FileSlice) -> Option<i64> {
+        if !self.is_same_file(other) {
+            return None;
+        }
+        i64::try_from(i128::from(other.start) - i128::from(self.start)).ok()
+    }
+
+    /// Identify the underlying file on disk
+    ///
+    /// See [`FileId`].  This calls `fstat`.  It's currently only supported
+    /// on unix; elsewhere it returns an `Unsupported` error.
+    pub fn file_identity(&self) -> std::io::Result<FileId> {
+        FileId::from_metadata(&self.file.metadata()?)
+    }
+
     pub fn is_empty(&self) -> bool {
         self.start == self.end
     }
@@ -111,137 +621,3557 @@ impl FileSlice {
         (self.end - self.start) as usize
     }
 
+    /// The number of bytes between the cursor and the end of the slice
+    ///
+    /// This is zero if the cursor has been moved beyond the end.
     pub fn bytes_remaining(&self) -> usize {
-        (self.end - self.cursor) as usize
+        self.end.saturating_sub(self.cursor) as usize
     }
 }
 
-impl Read for FileSlice {
-    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
-        let remaining = (self.end - self.cursor) as usize;
-        let buf = if buf.len() > remaining {
-            &mut buf[..remaining]
-        } else {
-            buf
-        };
-
-        let x;
+impl FileSlice {
+    /// Read from the underlying file at the given absolute offset, ignoring
+    /// both the cursor and the slice bounds
+    ///
+    /// This is a single call to the OS, so it may return a short read on any
+    /// platform.  Callers which need a full buffer use
+    /// [`FileSlice::pread_exact`] (or a loop of their own), which retries.
+    fn pread(&self, buf: &mut [u8], pos: u64) -> std::io::Result<usize> {
+        #[cfg(test)]
+        tests::count_pread();
         #[cfg(target_family = "unix")]
         {
             use std::os::unix::fs::FileExt;
-            x = self.file.read_at(buf, self.cursor)?;
+            self.file.read_at(buf, pos)
         }
         #[cfg(target_family = "windows")]
         {
             use std::os::windows::fs::FileExt;
-            x = self.file.seek_read(buf, self.cursor)?;
+            self.file.seek_read(buf, pos)
         }
-        #[cfg(target_family = "wasm")]
+        #[cfg(target_os = "wasi")]
         {
             use std::os::wasi::fs::FileExt;
-            x = self.file.read_at(buf, self.cursor)?;
+            self.file.read_at(buf, pos)
         }
+    }
 
-        self.cursor += x as u64;
-        Ok(x)
+    /// Like [`FileSlice::pread`], but reads into a buffer which may not have
+    /// been initialised
+    ///
+    /// Returns the number of bytes read; that many bytes at the start of
+    /// `buf` are now initialised.
+    fn pread_uninit(
+        &self,
+        buf: &mut [std::mem::MaybeUninit<u8>],
+        pos: u64,
+    ) -> std::io::Result<usize> {
+        #[cfg(all(test, target_family = "unix"))]
+        tests::count_pread();
+        #[cfg(target_family = "unix")]
+        {
+            use std::os::unix::io::AsRawFd;
+            let pos = pos
+                .try_into()
+                .map_err(|_| std::io::Error::other("Offset too large"))?;
+            // SAFETY: The fd is kept open by `self.file`, and `buf` is valid
+            // for writes of `buf.len()` bytes.  The kernel only writes to it.
+            match unsafe {
+                libc::pread(
+                    self.file.as_raw_fd(),
+                    buf.as_mut_ptr().cast(),
+                    buf.len(),
+                    pos,
+                )
+            } {
+                -1 => Err(std::io::Error::last_os_error()),
+                n => Ok(n as usize),
+            }
+        }
+        #[cfg(not(target_family = "unix"))]
+        {
+            buf.fill(std::mem::MaybeUninit::new(0));
+            // SAFETY: We've just initialised every byte of `buf`
+            let buf = unsafe { &mut *(buf as *mut [std::mem::MaybeUninit<u8>] as *mut [u8]) };
+            self.pread(buf, pos)
+        }
     }
-}
 
-impl Seek for FileSlice {
-    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
-        let cursor = match pos {
-            SeekFrom::Current(x) => i128::from(self.cursor) + i128::from(x),
-            SeekFrom::Start(x) => i128::from(self.start + x),
-            SeekFrom::End(x) => i128::from(self.end) + i128::from(x),
-        };
-        let cursor = match u64::try_from(cursor) {
-            Ok(x) if x >= self.start => x,
-            _ => {
-                return Err(std::io::Error::new(
-                    std::io::ErrorKind::Other,
-                    "Out of bounds",
-                ))
+    /// Fill `buf` from the underlying file, starting at the given absolute
+    /// offset
+    fn pread_exact(&self, mut buf: &mut [u8], mut pos: u64) -> std::io::Result<()> {
+        while !buf.is_empty() {
+            match self.pread(buf, pos) {
+                Ok(0) => return Err(std::io::ErrorKind::UnexpectedEof.into()),
+                Ok(n) => {
+                    buf = &mut buf[n..];
+                    pos += n as u64;
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => (),
+                Err(e) => return Err(e),
             }
-        };
-        self.cursor = cursor;
-        self.stream_position()
+        }
+        Ok(())
     }
 
-    fn stream_position(&mut self) -> std::io::Result<u64> {
-        Ok(self.cursor - self.start)
+    /// Read the given absolute range of the underlying file into a new `Vec`
+    ///
+    /// Unlike filling a `vec![0; end - start]` with `pread_exact`, the buffer
+    /// grows as the data arrives, so an `end` which is far beyond the end of
+    /// the file doesn't cause a huge allocation.  Returns an `UnexpectedEof`
+    /// error if the file ends before `end`.
+    fn pread_vec(&self, start: u64, end: u64) -> std::io::Result<Vec<u8>> {
+        let mut buf = vec![];
+        self.derive(start, end, start).read_to_end(&mut buf)?;
+        if (buf.len() as u64) < end - start {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+        Ok(buf)
     }
 }
 
+/// The size of the blocks used by methods which scan through a slice
+const BLOCK_SIZE: usize = 8 * 1024;
+
 impl FileSlice {
-    /// Expand the slice to cover the whole file
+    /// Read the last `n` bytes of the slice
     ///
-    /// This queries the underlying file for its current length, which may have
-    /// changed since this `FileSlice` was created.  Counter-intuitively, this
-    /// means that calling this method _could_ in theory cause the length of the
-    /// `FileSlice` to reduce (if the underlying file has been truncated).
-    pub fn expand(&mut self) {
-        self.start = 0;
-        self.end = self.file.metadata().unwrap().len();
+    /// If the slice is shorter than `n` bytes, the whole slice is returned.
+    /// The cursor is not used or modified.  Handy for formats which keep their
+    /// metadata in a footer.
+    pub fn read_last(&self, n: u64) -> std::io::Result<Vec<u8>> {
+        let n = n.min(self.end - self.start);
+        self.pread_vec(self.end - n, self.end)
     }
 
-    /// Try to get back the inner `File`
+    /// Read the given range of the slice
     ///
-    /// This only works if this `FileSlice` has no living clones.  If there are
-    /// other `FileSlices` using the same `File`, this method will return the
-    /// original `FileSlice` unmodified.
-    pub fn try_unwrap(self) -> Result<File, FileSlice> {
-        Arc::try_unwrap(self.file).map_err(|file| FileSlice {
-            file,
-            cursor: self.cursor,
-            start: self.start,
-            end: self.end,
-        })
+    /// The range is relative to the start of the slice, as with
+    /// [`FileSlice::slice`].  Unlike `slice`, however, the range is not
+    /// clamped: if it extends beyond the end of the slice, this returns an
+    /// `UnexpectedEof` error.  The cursor is not used or modified.
+    pub fn read_range<R: RangeBounds<u64>>(&self, range: R) -> std::io::Result<Vec<u8>> {
+        let (start, end) = self.resolve_range(range);
+        if end > self.end {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                "Range extends beyond the end of the slice",
+            ));
+        }
+        if start > end {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "Range is inverted",
+            ));
+        }
+        self.pread_vec(start, end)
     }
-}
 
-#[cfg(feature = "parquet")]
-mod parquet_impls {
-    use super::*;
-    use bytes::Bytes;
-    use parquet::file::reader::{ChunkReader, Length};
+    /// Read the `block_idx`th block of size `block_size`
+    ///
+    /// Blocks are counted from the start of the slice.  The last block may be
+    /// short, if the length of the slice isn't a multiple of `block_size`;
+    /// blocks beyond the end are empty.  The cursor is not used or modified.
+    pub fn read_block(&self, block_idx: u64, block_size: u64) -> std::io::Result<Vec<u8>> {
+        let start = block_idx.saturating_mul(block_size);
+        let mut slice = self.slice(start..start.saturating_add(block_size));
+        let mut buf = Vec::new();
+        slice.read_to_end(&mut buf)?;
+        Ok(buf)
+    }
 
-    impl Length for FileSlice {
-        fn len(&self) -> u64 {
-            self.end - self.start
-        }
+    /// Read everything from `offset` bytes into the slice up to the end,
+    /// appending it to `out`
+    ///
+    /// Returns the number of bytes read.  The cursor is not used or modified.
+    pub fn read_to_end_at(&self, offset: u64, out: &mut Vec<u8>) -> std::io::Result<usize> {
+        self.slice(offset..).read_to_end(out)
     }
 
-    impl ChunkReader for FileSlice {
-        type T = FileSlice;
+    /// Read into several buffers, starting `offset` bytes into the slice
+    ///
+    /// The buffers are filled in order, as with `Read::read_vectored`.  The
+    /// read stops at the end of the slice, and like `read`, it may return
+    /// fewer bytes than requested even before then.  Returns the number of
+    /// bytes read.  The cursor is not used or modified.
+    ///
+    /// On Linux this is a single `preadv` call, so you can fill (say) a
+    /// header and a body buffer with one syscall.  Elsewhere the buffers are
+    /// filled with a `pread` each.
+    pub fn read_vectored_at(
+        &self,
+        offset: u64,
+        bufs: &mut [std::io::IoSliceMut],
+    ) -> std::io::Result<usize> {
+        let pos = self.start.saturating_add(offset);
+        let remaining = self.end.saturating_sub(pos);
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        {
+            self.preadv(bufs, pos, remaining)
+        }
+        #[cfg(not(any(target_os = "linux", target_os = "android")))]
+        {
+            let mut n = 0;
+            for buf in bufs {
+                let want = (buf.len() as u64).min(remaining - n as u64) as usize;
+                if want == 0 {
+                    continue;
+                }
+                match self.pread(&mut buf[..want], pos + n as u64) {
+                    Ok(x) if x < want => return Ok(n + x),
+                    Ok(x) => n += x,
+                    // Report what we've got so far; the error will come up
+                    // again on the next call
+                    Err(_) if n > 0 => break,
+                    Err(e) => return Err(e),
+                }
+            }
+            Ok(n)
+        }
+    }
 
-        fn get_read(&self, start: u64) -> parquet::errors::Result<FileSlice> {
-            Ok(self.slice(start..self.end))
+    /// Read into `buf`, starting `offset` bytes into the slice, giving up
+    /// after `timeout`
+    ///
+    /// This is for files on network filesystems (NFS, FUSE, etc.), where a
+    /// read can hang indefinitely.  The read is performed on a new thread,
+    /// and if it hasn't finished within `timeout`, a `TimedOut` error is
+    /// returned.  As with `read`, the read stops at the end of the slice and
+    /// may be short.  The cursor is not used or modified.
+    ///
+    /// Note that a timed-out read isn't cancelled: the thread is left running
+    /// until the OS returns, and holds the file open until then.  If reads
+    /// keep timing out, these threads will pile up.
+    pub fn read_at_timeout(
+        &self,
+        buf: &mut [u8],
+        offset: u64,
+        timeout: std::time::Duration,
+    ) -> std::io::Result<usize> {
+        let slice = self.slice(offset..offset.saturating_add(buf.len() as u64));
+        let (tx, rx) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            let mut tmp = vec![0; slice.len()];
+            let result = loop {
+                match slice.pread(&mut tmp, slice.start) {
+                    Err(e) if e.kind() == std::io::ErrorKind::Interrupted => (),
+                    x => break x,
+                }
+            };
+            // If the receiver has gone, nobody's interested any more
+            let _ = tx.send(result.map(|n| {
+                tmp.truncate(n);
+                tmp
+            }));
+        });
+        match rx.recv_timeout(timeout) {
+            Ok(result) => {
+                let data = result?;
+                buf[..data.len()].copy_from_slice(&data);
+                Ok(data.len())
+            }
+            Err(_) => Err(std::io::Error::new(
+                std::io::ErrorKind::TimedOut,
+                "Read timed out",
+            )),
         }
+    }
 
-        fn get_bytes(&self, start: u64, length: usize) -> parquet::errors::Result<Bytes> {
-            let mut buf = vec![0; length];
-            self.slice(start..(start + length as u64))
-                .read_exact(&mut buf)?;
-            Ok(buf.into())
+    /// Read the whole slice into `buf`, replacing its contents
+    ///
+    /// `buf` is cleared first, but keeps its allocation, so you can reuse one
+    /// buffer for reading many slices without allocating each time.  Returns
+    /// the number of bytes read.  The cursor is not used or modified.
+    pub fn read_all_into(&self, buf: &mut Vec<u8>) -> std::io::Result<usize> {
+        buf.clear();
+        self.read_to_end_at(0, buf)
+    }
+
+    /// Check whether the slice begins with `expected`
+    ///
+    /// Returns `false` if the first `expected.len()` bytes differ, or if the
+    /// slice is too short to contain them.  This is the usual first step when
+    /// sniffing a file format.  The cursor is not used or modified.
+    pub fn check_magic(&self, expected: &[u8]) -> std::io::Result<bool> {
+        if expected.len() as u64 > self.end - self.start {
+            return Ok(false);
+        }
+        let mut buf = vec![0; expected.len()];
+        match self.pread_exact(&mut buf, self.start) {
+            Ok(()) => Ok(buf == expected),
+            // The file must have been truncated
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => Ok(false),
+            Err(e) => Err(e),
         }
     }
-}
 
-#[cfg(feature = "tar")]
-pub fn slice_tarball(
-    mut archive: tar::Archive<File>,
-) -> std::io::Result<impl Iterator<Item = (tar::Header, FileSlice)>> {
-    let headers = archive
-        .entries_with_seek()?
-        .map(move |entry| {
-            let entry = entry.unwrap();
-            let start = entry.raw_file_position();
-            let end = start + entry.size();
-            (entry.header().clone(), start, end)
+    /// Read the slice in pieces of `block` bytes, and apply `f` to each one
+    ///
+    /// This lets you transform a slice as it's read, without holding the whole
+    /// thing in memory.  Every piece is `block` bytes long, except possibly
+    /// the last.  The boundaries between pieces are arbitrary as far as the
+    /// contents are concerned, so `f` must either not care about them, or
+    /// carry over any partial unit itself.  The whole slice is covered,
+    /// regardless of the cursor, and iteration stops after the first error.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `block` is zero.
+    pub fn map_read<F, T>(&self, block: usize, mut f: F) -> impl Iterator<Item = std::io::Result<T>>
+    where
+        F: FnMut(&[u8]) -> T,
+    {
+        assert!(block > 0, "block size must be non-zero");
+        let slice = self.clone();
+        let mut pos = self.start;
+        let mut buf = vec![0; block.min(self.len())];
+        std::iter::from_fn(move || {
+            let want = (buf.len() as u64).min(slice.end.saturating_sub(pos)) as usize;
+            let mut n = 0;
+            while n < want {
+                match slice.pread(&mut buf[n..want], pos + n as u64) {
+                    // The file must have been truncated
+                    Ok(0) => break,
+                    Ok(x) => n += x,
+                    Err(e) if e.kind() == std::io::ErrorKind::Interrupted => (),
+                    Err(e) => {
+                        pos = slice.end;
+                        return Some(Err(e));
+                    }
+                }
+            }
+            if n == 0 {
+                pos = slice.end;
+                return None;
+            }
+            pos += n as u64;
+            Some(Ok(f(&buf[..n])))
         })
-        .collect::<Vec<_>>();
-    let file = FileSlice::new(archive.into_inner());
-    Ok(headers
-        .into_iter()
-        .map(move |(header, start, end)| (header, file.slice(start..end))))
+    }
+
+    /// Iterate over the bytes of the slice
+    ///
+    /// Unlike [`Read::bytes`], which issues one `pread` per byte, this reads
+    /// the slice in 8 KiB blocks, so scanning a slice of `n` bytes costs
+    /// roughly `n / 8192` syscalls.  The iterator always covers the whole
+    /// slice, from `start` to `end`, regardless of the current cursor.
+    pub fn bytes_iter(&self) -> impl Iterator<Item = std::io::Result<u8>> {
+        BytesIter {
+            slice: self.clone(),
+            pos: self.start,
+            buf: vec![0; BLOCK_SIZE.min(self.len())],
+            buf_pos: 0,
+            buf_len: 0,
+            done: false,
+        }
+    }
+}
+
+impl FileSlice {
+    /// Shrink the slice so that it ends just before the first occurrence of
+    /// `delim`
+    ///
+    /// The search starts from the beginning of the slice.  Returns `true` if
+    /// `delim` was found (and the slice was truncated), or `false` if it
+    /// wasn't (in which case the slice is left unchanged).
+    pub fn truncate_at_first(&mut self, delim: u8) -> std::io::Result<bool> {
+        match self.find_byte(delim, self.start)? {
+            Some(pos) => {
+                self.end = pos;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Shrink the slice so that it doesn't end with any copies of `byte`
+    ///
+    /// This is for stripping the padding off the end of a record, such as the
+    /// NULs which pad out tar members.  The slice is read backwards from the
+    /// end in blocks, so only the padding (plus a little) is read.  If the
+    /// slice consists entirely of `byte`, it ends up empty.
+    pub fn trim_trailing(&mut self, byte: u8) -> std::io::Result<()> {
+        let mut buf = vec![0; BLOCK_SIZE.min(self.len())];
+        while self.end > self.start {
+            let n = (buf.len() as u64).min(self.end - self.start) as usize;
+            let pos = self.end - n as u64;
+            self.pread_exact(&mut buf[..n], pos)?;
+            match buf[..n].iter().rposition(|&x| x != byte) {
+                Some(i) => {
+                    self.end = pos + i as u64 + 1;
+                    return Ok(());
+                }
+                None => self.end = pos,
+            }
+        }
+        Ok(())
+    }
+
+    /// Read a NUL-terminated string from the cursor
+    ///
+    /// Returns the bytes up to (but not including) the NUL, and leaves the
+    /// cursor just after the NUL.  If the end of the slice is reached without
+    /// finding a NUL, everything up to the end is returned; this is
+    /// indistinguishable from a string which is terminated by the end of the
+    /// slice.
+    pub fn read_cstr(&mut self) -> std::io::Result<Vec<u8>> {
+        let start = self.cursor.min(self.end);
+        let (end, next) = match self.find_byte(0, start)? {
+            Some(pos) => (pos, pos + 1),
+            None => (self.end, self.end),
+        };
+        let buf = self.pread_vec(start, end)?;
+        self.cursor = self.cursor.max(next);
+        Ok(buf)
+    }
+
+    /// Find the offsets at which the records of a CSV file begin
+    ///
+    /// Records are separated by `\n` (any preceding `\r` is left as part of
+    /// the record).  Fields are separated by `delim`, and may be quoted with
+    /// `quote`, in which case they can contain `delim`s and newlines.  A
+    /// doubled `quote` within a quoted field is an escaped quote.
+    ///
+    /// The returned offsets are relative to the start of the slice, so you
+    /// can `slice` between consecutive offsets to get individual records.
+    /// The first offset is always `0` (unless the slice is empty).  The
+    /// cursor is not used or modified.
+    pub fn csv_record_offsets(&self, delim: u8, quote: u8) -> std::io::Result<Vec<u64>> {
+        let len = self.end - self.start;
+        let mut offsets = vec![];
+        if len > 0 {
+            offsets.push(0);
+        }
+        let mut field_start = true;
+        let mut quoted_field = false;
+        let mut in_quotes = false;
+        for (i, b) in (1..).zip(self.bytes_iter()) {
+            let b = b?;
+            if in_quotes {
+                in_quotes = b != quote;
+            } else if b == quote && (field_start || quoted_field) {
+                in_quotes = true;
+                quoted_field = true;
+                field_start = false;
+            } else if b == delim || b == b'\n' {
+                if b == b'\n' && i < len {
+                    offsets.push(i);
+                }
+                field_start = true;
+                quoted_field = false;
+            } else {
+                field_start = false;
+            }
+        }
+        Ok(offsets)
+    }
+
+    /// Split the slice into records separated by `delim`
+    ///
+    /// Each record is yielded as a sub-slice, not including the delimiter.
+    /// The final record doesn't need to be followed by a delimiter, but if it
+    /// is, no empty record is yielded after it.  Consecutive delimiters
+    /// produce empty records.  The whole slice is covered, regardless of the
+    /// cursor, and iteration stops after the first error.
+    ///
+    /// For example, splitting an NDJSON file at `b'\n'` gives one slice per
+    /// JSON document.
+    pub fn split_at_delimiters(
+        &self,
+        delim: u8,
+    ) -> impl Iterator<Item = std::io::Result<FileSlice>> {
+        let slice = self.clone();
+        let mut pos = self.start;
+        std::iter::from_fn(move || {
+            if pos >= slice.end {
+                return None;
+            }
+            let (end, next) = match slice.find_byte(delim, pos) {
+                Ok(Some(x)) => (x, x + 1),
+                Ok(None) => (slice.end, slice.end),
+                Err(e) => {
+                    pos = slice.end;
+                    return Some(Err(e));
+                }
+            };
+            let record = slice.derive(pos, end, pos);
+            pos = next;
+            Some(Ok(record))
+        })
+    }
+
+    /// Count the number of times `byte` appears in the slice
+    ///
+    /// This is handy for eg. counting the lines in a text file.  The slice is
+    /// read in blocks, and the cursor is not used or modified.
+    pub fn count_occurrences(&self, byte: u8) -> std::io::Result<u64> {
+        let mut count = 0;
+        self.scan_blocks(self.start, |_, block| {
+            count += block.iter().filter(|&&x| x == byte).count() as u64;
+            true
+        })?;
+        Ok(count)
+    }
+
+    /// Find the first occurrence of `byte` at or after the absolute offset
+    /// `from`, returning its absolute offset
+    fn find_byte(&self, byte: u8, from: u64) -> std::io::Result<Option<u64>> {
+        let mut found = None;
+        self.scan_blocks(from, |pos, block| {
+            found = block
+                .iter()
+                .position(|&x| x == byte)
+                .map(|i| pos + i as u64);
+            found.is_none()
+        })?;
+        Ok(found)
+    }
+
+    /// Read from the absolute offset `from` to the end of the slice in blocks,
+    /// passing each block and its absolute offset to `f`
+    ///
+    /// Stops early if `f` returns `false`.
+    fn scan_blocks(
+        &self,
+        mut from: u64,
+        mut f: impl FnMut(u64, &[u8]) -> bool,
+    ) -> std::io::Result<()> {
+        let mut buf = vec![0; BLOCK_SIZE.min(self.len())];
+        while from < self.end {
+            let want = (buf.len() as u64).min(self.end - from) as usize;
+            let n = match self.pread(&mut buf[..want], from) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            if !f(from, &buf[..n]) {
+                break;
+            }
+            from += n as u64;
+        }
+        Ok(())
+    }
+}
+
+impl FileSlice {
+    /// Hint that the next `len` bytes after the cursor will be read soon
+    ///
+    /// This warms the page cache so that subsequent reads don't have to wait
+    /// for the disk.  On Linux it issues `readahead(2)`; elsewhere, it reads
+    /// the data on a background thread and throws it away.  Either way, it
+    /// returns immediately and doesn't affect the cursor.  It's only a hint,
+    /// so any errors are ignored.
+    pub fn prefetch(&self, len: u64) {
+        let start = self.cursor.min(self.end);
+        let end = start.saturating_add(len).min(self.end);
+        if start == end {
+            return;
+        }
+        #[cfg(target_os = "linux")]
+        {
+            use std::os::unix::io::AsRawFd;
+            if let Ok(offset) = start.try_into() {
+                // SAFETY: The fd is kept open by `self.file`
+                unsafe { libc::readahead(self.file.as_raw_fd(), offset, (end - start) as usize) };
+            }
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            let slice = self.slice((start - self.start)..(end - self.start));
+            std::thread::spawn(move || {
+                let mut buf = vec![0; BLOCK_SIZE];
+                let mut pos = slice.start;
+                while pos < slice.end {
+                    let want = buf.len().min((slice.end - pos) as usize);
+                    match slice.pread(&mut buf[..want], pos) {
+                        Ok(0) | Err(_) => break,
+                        Ok(n) => pos += n as u64,
+                    }
+                }
+            });
+        }
+    }
+}
+
+struct BytesIter {
+    slice: FileSlice,
+    /// The absolute file offset of the end of `buf`
+    pos: u64,
+    buf: Vec<u8>,
+    buf_pos: usize,
+    buf_len: usize,
+    done: bool,
+}
+
+impl Iterator for BytesIter {
+    type Item = std::io::Result<u8>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.buf_pos == self.buf_len {
+            if self.done || self.pos >= self.slice.end {
+                return None;
+            }
+            let want = self.buf.len().min((self.slice.end - self.pos) as usize);
+            match self.slice.pread(&mut self.buf[..want], self.pos) {
+                Ok(0) => {
+                    self.done = true;
+                    return None;
+                }
+                Ok(n) => {
+                    self.pos += n as u64;
+                    self.buf_pos = 0;
+                    self.buf_len = n;
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => return self.next(),
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+        let x = self.buf[self.buf_pos];
+        self.buf_pos += 1;
+        Some(Ok(x))
+    }
+}
+
+impl Read for FileSlice {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        // The cursor may have been moved beyond the end (even beyond what the
+        // OS accepts as an offset), in which case there's nothing left to read
+        let remaining = self.end.saturating_sub(self.cursor);
+        if remaining == 0 {
+            return Ok(0);
+        }
+        // Fast path: the whole buffer fits within the slice
+        let buf = if buf.len() as u64 <= remaining {
+            buf
+        } else {
+            &mut buf[..remaining as usize]
+        };
+        let x = self.pread(buf, self.cursor)?;
+        self.cursor += x as u64;
+        Ok(x)
+    }
+
+    fn read_to_end(&mut self, buf: &mut Vec<u8>) -> std::io::Result<usize> {
+        // The slice's bounds tell us the most we could read, but they may
+        // not match the file (eg. after `with_file_len` or `from_parts`, or
+        // if the file was truncated).  So we only reserve up to
+        // `PREALLOC_LIMIT` up-front, and grow the buffer as the data
+        // actually arrives.  Reading stops at `end` even if the file has
+        // grown in the meantime.
+        const PREALLOC_LIMIT: u64 = 1024 * 1024;
+        let start_len = buf.len();
+        loop {
+            let remaining = self.end.saturating_sub(self.cursor);
+            if remaining == 0 {
+                break;
+            }
+            if buf.len() == buf.capacity() {
+                let read = (buf.len() - start_len) as u64;
+                buf.reserve(remaining.min(read.max(PREALLOC_LIMIT)) as usize);
+            }
+            let spare = buf.spare_capacity_mut();
+            let want = (spare.len() as u64).min(remaining) as usize;
+            match self.pread_uninit(&mut spare[..want], self.cursor) {
+                // The file must be shorter than the slice claims
+                Ok(0) => break,
+                Ok(n) => {
+                    // SAFETY: `pread_uninit` initialised the first `n` bytes
+                    // of the spare capacity
+                    unsafe { buf.set_len(buf.len() + n) };
+                    self.cursor += n as u64;
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => (),
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(buf.len() - start_len)
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> std::io::Result<()> {
+        // We know in advance whether the slice is long enough, so there's no
+        // need to do any I/O to find out
+        if buf.len() as u64 > self.end.saturating_sub(self.cursor) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                "Slice is too short",
+            ));
+        }
+        self.pread_exact(buf, self.cursor)?;
+        self.cursor += buf.len() as u64;
+        Ok(())
+    }
+}
+
+impl FileSlice {
+    /// Get the byte at the cursor, without advancing the cursor
+    ///
+    /// Returns `None` if the cursor is at (or beyond) the end of the slice.
+    pub fn peek_byte(&self) -> std::io::Result<Option<u8>> {
+        if self.cursor >= self.end {
+            return Ok(None);
+        }
+        let mut buf = [0];
+        loop {
+            match self.pread(&mut buf, self.cursor) {
+                Ok(0) => return Ok(None),
+                Ok(_) => return Ok(Some(buf[0])),
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => (),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Fill `buf`, unless the cursor is already at the end of the slice
+    ///
+    /// This is for reading fixed-size records in a loop.  Returns `Ok(None)`
+    /// if there's nothing left to read (a clean end), `Ok(Some(()))` if `buf`
+    /// was filled, or an `UnexpectedEof` error if there's some data left, but
+    /// not enough to fill `buf` (a truncated record).
+    pub fn read_exact_or_eof(&mut self, buf: &mut [u8]) -> std::io::Result<Option<()>> {
+        if self.cursor >= self.end {
+            return Ok(None);
+        }
+        self.read_exact(buf)?;
+        Ok(Some(()))
+    }
+
+    /// Read until `buf` is full or the end of the slice is reached
+    ///
+    /// Returns the number of bytes read, which is less than `buf.len()` only
+    /// if the end of the slice was reached.  Unlike [`Read::read`], this
+    /// never returns a short count just because the OS returned a short read,
+    /// and it retries on `EINTR`.
+    pub fn read_full(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let mut n = 0;
+        while n < buf.len() {
+            match self.read(&mut buf[n..]) {
+                Ok(0) => break,
+                Ok(x) => n += x,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => (),
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(n)
+    }
+
+    /// Read at least `min` bytes into `buf`
+    ///
+    /// This keeps reading until at least `min` bytes have been read, but may
+    /// read as many as `buf.len()`.  Returns the number of bytes read.  If the
+    /// end of the slice is reached before `min` bytes have been read, an
+    /// `UnexpectedEof` error is returned (and the bytes which were read are
+    /// lost).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `min > buf.len()`.
+    pub fn read_at_least(&mut self, buf: &mut [u8], min: usize) -> std::io::Result<usize> {
+        assert!(min <= buf.len(), "min ({min}) is bigger than the buffer");
+        let mut n = 0;
+        while n < min {
+            match self.read(&mut buf[n..]) {
+                Ok(0) => return Err(std::io::ErrorKind::UnexpectedEof.into()),
+                Ok(x) => n += x,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => (),
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(n)
+    }
+
+    /// Read exactly `len` bytes from the cursor into a new boxed slice
+    ///
+    /// Unlike reading into a `Vec`, the returned buffer has no spare capacity,
+    /// which makes it a better fit for long-lived buffers.  If there are fewer
+    /// than `len` bytes left in the slice, an `UnexpectedEof` error is
+    /// returned without doing any I/O, and the cursor is not moved.
+    pub fn read_boxed(&mut self, len: usize) -> std::io::Result<Box<[u8]>> {
+        if len as u64 > self.end.saturating_sub(self.cursor) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                "Slice is too short",
+            ));
+        }
+        let mut buf = vec![0; len].into_boxed_slice();
+        self.read_exact(&mut buf)?;
+        Ok(buf)
+    }
+
+    /// Read `count` records by calling `f` repeatedly
+    ///
+    /// `f` is given the slice, and should read one record from the cursor
+    /// (advancing it past the record).  The records are collected into a
+    /// `Vec`.  If `f` returns an error, this stops and returns it straight
+    /// away, with the cursor wherever `f` left it.
+    pub fn read_sequence<T, F>(&mut self, count: usize, mut f: F) -> std::io::Result<Vec<T>>
+    where
+        F: FnMut(&mut FileSlice) -> std::io::Result<T>,
+    {
+        // `count` may have come from an untrusted header, so don't reserve
+        // more than could plausibly be needed
+        let mut out = Vec::with_capacity(count.min(self.bytes_remaining()));
+        for _ in 0..count {
+            out.push(f(self)?);
+        }
+        Ok(out)
+    }
+
+    /// Read up to `len` bytes from the cursor into a new `Vec`
+    ///
+    /// This is like calling [`FileSlice::read_full`] with a `vec![0; len]`,
+    /// but it skips zeroing the buffer first, which saves time for large
+    /// reads.  The `Vec` is shorter than `len` only if the end of the slice
+    /// was reached.  If an error occurs, the bytes read so far are lost (but
+    /// the cursor has still moved past them).
+    pub fn read_uninit_vec(&mut self, len: usize) -> std::io::Result<Vec<u8>> {
+        let len = (len as u64).min(self.end.saturating_sub(self.cursor)) as usize;
+        let mut buf = Vec::with_capacity(len);
+        while buf.len() < len {
+            let want = len - buf.len();
+            match self.pread_uninit(&mut buf.spare_capacity_mut()[..want], self.cursor) {
+                // The file must have been truncated
+                Ok(0) => break,
+                Ok(n) => {
+                    // SAFETY: `pread_uninit` initialised the first `n` bytes
+                    // of the spare capacity
+                    unsafe { buf.set_len(buf.len() + n) };
+                    self.cursor += n as u64;
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => (),
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(buf)
+    }
+}
+
+impl Seek for FileSlice {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        // Done in i128 so that nothing can overflow; anything which doesn't
+        // fit back in a u64 is out of bounds
+        let cursor = match pos {
+            SeekFrom::Current(x) => i128::from(self.cursor) + i128::from(x),
+            SeekFrom::Start(x) => i128::from(self.start) + i128::from(x),
+            SeekFrom::End(x) => i128::from(self.end) + i128::from(x),
+        };
+        let cursor = match u64::try_from(cursor) {
+            Ok(x) if x >= self.start => x,
+            _ => return Err(std::io::Error::other("Out of bounds")),
+        };
+        self.cursor = cursor;
+        self.stream_position()
+    }
+
+    /// The position of the cursor, relative to the start of the slice
+    ///
+    /// It's possible to seek beyond the end of the slice, in which case this
+    /// will be greater than `len()`.  See [`FileSlice::position_clamped`] if
+    /// that's a problem.
+    fn stream_position(&mut self) -> std::io::Result<u64> {
+        Ok(self.cursor - self.start)
+    }
+}
+
+impl FileSlice {
+    /// Move the cursor forwards or backwards by `offset` bytes
+    ///
+    /// This is the same as `seek(SeekFrom::Current(offset))`, but doesn't
+    /// return the new position.  As with `seek`, moving the cursor before the
+    /// start of the slice is an error (and leaves the cursor unchanged).
+    pub fn seek_relative(&mut self, offset: i64) -> std::io::Result<()> {
+        self.seek(SeekFrom::Current(offset))?;
+        Ok(())
+    }
+
+    /// Move the cursor to the given fraction of the way through the slice
+    ///
+    /// `0.0` is the beginning and `1.0` is the end; values outside this range
+    /// are clamped.  Returns the new position, relative to the start of the
+    /// slice.
+    pub fn seek_fraction(&mut self, frac: f64) -> u64 {
+        let len = self.end - self.start;
+        let pos = ((len as f64 * frac.clamp(0.0, 1.0)) as u64).min(len);
+        self.cursor = self.start + pos;
+        pos
+    }
+
+    /// Box the slice up as a `dyn ReadSeek`
+    ///
+    /// This is for passing a slice to APIs which take a `Box<dyn Read +
+    /// Seek>`, or for storing slices alongside other readers.
+    pub fn boxed(self) -> Box<dyn ReadSeek> {
+        Box::new(self)
+    }
+}
+
+/// A reader which can also seek
+///
+/// `dyn Read + Seek` isn't allowed, since only one non-auto trait can be used
+/// in a trait object, so this combines the two.  It's implemented for
+/// everything which implements both `Read` and `Seek`.
+pub trait ReadSeek: Read + Seek {}
+
+impl<T: Read + Seek + ?Sized> ReadSeek for T {}
+
+impl FileSlice {
+    /// Expand the slice to cover the whole file
+    ///
+    /// This queries the underlying file for its current length, which may have
+    /// changed since this `FileSlice` was created.  Counter-intuitively, this
+    /// means that calling this method _could_ in theory cause the length of the
+    /// `FileSlice` to reduce (if the underlying file has been truncated).
+    ///
+    /// The cursor stays at the same absolute position in the file.  Since the
+    /// start of the slice moves to the start of the file, this means that the
+    /// cursor's position _relative to the start of the slice_ (as reported by
+    /// `stream_position()`) increases by the old value of `start_pos()`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the length of the file can't be queried.  See
+    /// [`FileSlice::try_expand`] for a non-panicking version.
+    pub fn expand(&mut self) {
+        self.try_expand().unwrap()
+    }
+
+    /// Like [`FileSlice::expand`], but returns an error if the length of the
+    /// file can't be queried
+    ///
+    /// If an error is returned, the slice is unchanged.
+    pub fn try_expand(&mut self) -> std::io::Result<()> {
+        self.end = self.file.metadata()?.len();
+        self.start = 0;
+        Ok(())
+    }
+
+    /// Move the end of the slice forward by `delta` bytes, without going past
+    /// the end of the file
+    ///
+    /// This is for when you know how much has been appended to the file (say,
+    /// because the writer told you).  The file's length is still queried, but
+    /// only to cap the new end; see [`FileSlice::expand_by_unchecked`] to skip
+    /// that.  As with [`FileSlice::expand`], if the file has been truncated
+    /// the slice may end up shorter than before.  The start and the cursor
+    /// are unchanged.
+    ///
+    /// Returns an error if the length of the file can't be queried, in which
+    /// case the slice is unchanged.
+    pub fn expand_by(&mut self, delta: u64) -> std::io::Result<()> {
+        let file_len = self.file.metadata()?.len();
+        self.end = self.end.saturating_add(delta).min(file_len).max(self.start);
+        Ok(())
+    }
+
+    /// Move the end of the slice forward by `delta` bytes, trusting that the
+    /// file is long enough
+    ///
+    /// This doesn't do any I/O.  If the file turns out to be shorter than the
+    /// new end, reads near the end of the slice will come back short.
+    pub fn expand_by_unchecked(&mut self, delta: u64) {
+        self.end = self.end.saturating_add(delta);
+    }
+
+    /// Shrink the slice so that it doesn't extend beyond the end of the file
+    ///
+    /// If the file has been truncated since the slice was made, reads near
+    /// the end would come back short; this queries the file's length and
+    /// moves the end of the slice back to match, so that `len()` is accurate
+    /// again.  It never moves the end forwards (see [`FileSlice::expand`] for
+    /// that), and never moves it before the start.
+    pub fn shrink_to_readable(&mut self) -> std::io::Result<()> {
+        let file_len = self.file.metadata()?.len();
+        self.end = self.end.min(file_len).max(self.start);
+        Ok(())
+    }
+
+    /// Check that the underlying file is still long enough to contain the
+    /// slice
+    ///
+    /// Returns `false` if the file has been truncated such that it now ends
+    /// before the end of the slice, meaning that reads near the end would
+    /// come back short.  Unlike [`FileSlice::expand`], this doesn't modify the
+    /// slice.
+    pub fn verify_len(&self) -> std::io::Result<bool> {
+        Ok(self.file.metadata()?.len() >= self.end)
+    }
+
+    /// The number of bytes which can actually be read from the cursor right
+    /// now
+    ///
+    /// This is usually the same as [`FileSlice::bytes_remaining`], but it
+    /// also takes the current length of the file into account: if the file
+    /// has been truncated so that it ends before the end of the slice, this
+    /// is smaller.  The file's length is queried with `fstat`.
+    pub fn readable_bytes(&self) -> std::io::Result<u64> {
+        let file_len = self.file.metadata()?.len();
+        Ok(self.end.min(file_len).saturating_sub(self.cursor))
+    }
+
+    /// Query the metadata of the underlying file
+    ///
+    /// Note that this describes the whole file, not the slice: in particular,
+    /// `metadata()?.len()` is the length of the file, not `len()`.
+    pub fn metadata(&self) -> std::io::Result<std::fs::Metadata> {
+        self.file.metadata()
+    }
+
+    /// Whether the underlying file is a regular file
+    ///
+    /// A `FileSlice` can wrap any `File`, including pipes and character
+    /// devices, but for those the length reported by the OS (and hence
+    /// `len()`) is meaningless, and positioned reads may fail.  This checks
+    /// the file type with `fstat`.
+    pub fn is_regular_file(&self) -> std::io::Result<bool> {
+        Ok(self.file.metadata()?.file_type().is_file())
+    }
+
+    /// Check that the given range can be read in full
+    ///
+    /// The range is relative to the start of the slice.  This returns an
+    /// `UnexpectedEof` error if the range extends beyond the end of the slice,
+    /// or beyond the current end of the file (say, because it's been
+    /// truncated), and an `InvalidInput` error if it's inverted.  It's handy
+    /// for checking up-front before issuing a batch of reads.  The file's
+    /// length is queried with `fstat`.
+    pub fn ensure_readable(&self, range: Range<u64>) -> std::io::Result<()> {
+        let (start, end) = self.resolve_range(range);
+        if start > end {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "Range is inverted",
+            ));
+        }
+        if end > self.end {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                "Range extends beyond the end of the slice",
+            ));
+        }
+        if end > self.file.metadata()?.len() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                "Range extends beyond the end of the file",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Decompose the slice into `(file, start, end, cursor)`
+    ///
+    /// The offsets are absolute.  Use [`FileSlice::from_parts`] to put it back
+    /// together again.  (The reassembled slice won't share any mapping made by
+    /// `shared_mmap` with other slices of the file.)
+    pub fn into_parts(self) -> (Arc<File>, u64, u64, u64) {
+        (self.file, self.start, self.end, self.cursor)
+    }
+
+    /// Hint that the slice won't be read again any time soon
+    ///
+    /// This lets the kernel drop the slice's pages from the page cache, which
+    /// is polite when doing a one-off scan of a large file.  Uses
+    /// `posix_fadvise(POSIX_FADV_DONTNEED)` where available, and is a no-op
+    /// elsewhere.
+    pub fn advise_dontneed(&self) -> std::io::Result<()> {
+        self.advise_abs(self.start, self.end, Advice::DontNeed)
+    }
+
+    /// Wrap the slice in a `BufReader` with the given capacity
+    pub fn into_bufread(self, cap: usize) -> std::io::BufReader<FileSlice> {
+        std::io::BufReader::with_capacity(cap, self)
+    }
+
+    /// Wrap the slice in a `BufReader` with a 64 KiB buffer
+    ///
+    /// This is larger than `BufReader`'s usual default, since each refill of
+    /// the buffer costs a syscall.
+    pub fn into_bufread_default(self) -> std::io::BufReader<FileSlice> {
+        self.into_bufread(64 * 1024)
+    }
+
+    /// Make a copy of the slice which doesn't share its `File` with `self`
+    ///
+    /// The copy has the same bounds and cursor, but is backed by a new fd
+    /// (made with `File::try_clone`).  This means it keeps working after the
+    /// original and all its clones have been dropped or unwrapped (see
+    /// [`FileSlice::try_unwrap`]), which is handy for passing a slice to a
+    /// long-lived background task.  Bear in mind that it costs an extra fd,
+    /// which is exactly what `FileSlice` is usually trying to avoid.
+    pub fn detach(&self) -> std::io::Result<FileSlice> {
+        let file = Arc::new(self.file.try_clone()?);
+        Ok(FileSlice::from_file(
+            file,
+            self.start,
+            self.end,
+            self.cursor,
+        ))
+    }
+
+    /// Switch the slice over to a different `File`, keeping its bounds and
+    /// cursor
+    ///
+    /// This is for when the file has been reopened (say, after log rotation
+    /// or an atomic rename), and you want the same region of the new fd.
+    /// It's up to you to make sure that the new file has compatible
+    /// contents.  Only this slice is affected; its clones keep using the old
+    /// file.  Any shared mapping made by `shared_mmap` isn't carried over.
+    pub fn rebase(&mut self, new_file: File) {
+        *self = FileSlice::from_file(Arc::new(new_file), self.start, self.end, self.cursor);
+    }
+
+    /// Hint that the slice will be accessed in a random order
+    ///
+    /// This tells the kernel not to bother reading ahead, which is a good
+    /// idea for formats like parquet which jump around a lot.  Uses
+    /// `posix_fadvise(POSIX_FADV_RANDOM)` where available, and is a no-op
+    /// elsewhere.
+    pub fn advise_random(&self) -> std::io::Result<()> {
+        self.advise_abs(self.start, self.end, Advice::Random)
+    }
+
+    /// Ask the kernel to read ahead more aggressively for this slice
+    ///
+    /// This marks the slice as being read sequentially (which, on Linux,
+    /// doubles the kernel's readahead window), and asks for the next `bytes`
+    /// bytes after the cursor to be read in straight away.  Uses
+    /// `posix_fadvise` where available, and is a no-op elsewhere.  It's only a
+    /// hint, and doesn't affect the data which is read.
+    pub fn set_readahead(&self, bytes: u64) -> std::io::Result<()> {
+        self.advise_abs(self.start, self.end, Advice::Sequential)?;
+        let start = self.cursor.min(self.end);
+        let end = start.saturating_add(bytes).min(self.end);
+        self.advise_abs(start, end, Advice::WillNeed)
+    }
+
+    /// Tell the kernel how part of the slice is going to be accessed
+    ///
+    /// The range is relative to the start of the slice, and is clamped to it.
+    /// This lets you eg. ask for the footer of a file to be read in
+    /// (`WillNeed`) while dropping the body from the page cache
+    /// (`DontNeed`).  Uses `posix_fadvise` where available, and is a no-op
+    /// elsewhere.  It's only a hint, and doesn't affect the data which is
+    /// read.
+    pub fn advise_range(&self, range: Range<u64>, advice: Advice) -> std::io::Result<()> {
+        let (start, end) = self.resolve_range(range);
+        let start = start.min(self.end);
+        let end = end.min(self.end);
+        self.advise_abs(start, end, advice)
+    }
+
+    /// Apply `advice` to the given absolute range of the file
+    fn advise_abs(&self, start: u64, end: u64, advice: Advice) -> std::io::Result<()> {
+        #[cfg(any(target_os = "linux", target_os = "android", target_os = "freebsd"))]
+        {
+            let advice = match advice {
+                Advice::Normal => libc::POSIX_FADV_NORMAL,
+                Advice::Sequential => libc::POSIX_FADV_SEQUENTIAL,
+                Advice::Random => libc::POSIX_FADV_RANDOM,
+                Advice::WillNeed => libc::POSIX_FADV_WILLNEED,
+                Advice::DontNeed => libc::POSIX_FADV_DONTNEED,
+                Advice::NoReuse => libc::POSIX_FADV_NOREUSE,
+            };
+            self.fadvise(start, end, advice)
+        }
+        #[cfg(not(any(target_os = "linux", target_os = "android", target_os = "freebsd")))]
+        {
+            let _ = (start, end, advice);
+            Ok(())
+        }
+    }
+
+    /// Try to get back the inner `File`
+    ///
+    /// This only works if this `FileSlice` has no living clones.  If there are
+    /// other `FileSlices` using the same `File`, this method will return the
+    /// original `FileSlice` unmodified.
+    pub fn try_unwrap(self) -> Result<File, FileSlice> {
+        Arc::try_unwrap(self.file).map_err(|file| FileSlice {
+            file,
+            cursor: self.cursor,
+            start: self.start,
+            end: self.end,
+            #[cfg(all(unix, feature = "mmap"))]
+            mmap: self.mmap,
+        })
+    }
+
+    /// Copy the contents of the slice into `dst`
+    ///
+    /// The whole slice is copied, regardless of the cursor, and it's written
+    /// at `dst`'s current position.  On Linux, this uses `copy_file_range`,
+    /// so the data doesn't need to pass through userspace (and may not even
+    /// need to be copied, on filesystems which support reflinks).  Elsewhere,
+    /// or if `copy_file_range` isn't supported for this pair of files, it
+    /// falls back to a buffered copy.  Returns the number of bytes copied,
+    /// which is less than `len()` only if the file has been truncated.
+    pub fn copy_into(&self, dst: &mut File) -> std::io::Result<u64> {
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        let pos = self.copy_file_range(self.start, dst)?;
+        #[cfg(not(any(target_os = "linux", target_os = "android")))]
+        let pos = self.start;
+        let mut rest = self.derive(pos, self.end, pos);
+        let n = std::io::copy(&mut rest, dst)?;
+        Ok(pos - self.start + n)
+    }
+
+    /// Shrink the underlying file so that it ends where the slice ends
+    ///
+    /// This truncates the file to `end_pos()` bytes.  If `punch_prefix` is
+    /// set then, on Linux, the part of the file before `start_pos()` is also
+    /// deallocated (with `FALLOC_FL_PUNCH_HOLE`), so it reads back as zeros
+    /// and takes up no space on disk; if the filesystem doesn't support this,
+    /// or on other platforms, it's left alone.
+    ///
+    /// Note that this modifies the file itself, so it affects all other
+    /// slices of it (including clones of this one).  The file must have been
+    /// opened for writing.
+    pub fn truncate_file_to_slice(&self, punch_prefix: bool) -> std::io::Result<()> {
+        self.file.set_len(self.end)?;
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        if punch_prefix {
+            self.punch_hole(0, self.start)?;
+        }
+        #[cfg(not(any(target_os = "linux", target_os = "android")))]
+        let _ = punch_prefix;
+        Ok(())
+    }
+}
+
+#[cfg(unix)]
+mod unix;
+
+mod cached;
+mod concat;
+mod follow;
+mod pool;
+mod progress;
+mod shared;
+pub use cached::CachedFileSlice;
+pub use concat::concat;
+pub use follow::FollowingFileSlice;
+pub use pool::FileSlicePool;
+pub use progress::ProgressFileSlice;
+pub use shared::{SharedFileSlice, SyncFileSlice};
+
+#[cfg(feature = "async-fs")]
+mod async_fs;
+
+#[cfg(feature = "zstd")]
+mod zstd;
+#[cfg(feature = "zstd")]
+pub use zstd::FrameInfo;
+
+#[cfg(feature = "testing")]
+mod testing;
+
+#[cfg(all(unix, feature = "mmap"))]
+mod mmap;
+#[cfg(all(unix, feature = "mmap"))]
+pub use mmap::{MappedSlice, MappedWindow};
+
+#[cfg(feature = "zerocopy")]
+impl FileSlice {
+    /// Read a `T` from the cursor
+    ///
+    /// This reads `size_of::<T>()` bytes and reinterprets them as a `T`,
+    /// which is handy for binary headers represented as `#[repr(C)]` structs.
+    /// The bytes don't need to be aligned.  If there are too few bytes left in
+    /// the slice, an `UnexpectedEof` error is returned and the cursor is not
+    /// moved.
+    pub fn read_struct<T: zerocopy::FromBytes>(&mut self) -> std::io::Result<T> {
+        let mut buf = vec![0; std::mem::size_of::<T>()];
+        self.read_exact(&mut buf)?;
+        // The buffer is exactly the right size, so this can't fail
+        Ok(T::read_from_bytes(&buf).unwrap())
+    }
+}
+
+#[cfg(feature = "parquet")]
+mod parquet_impls {
+    use super::*;
+    use bytes::Bytes;
+    use parquet::file::reader::{ChunkReader, Length};
+
+    impl Length for FileSlice {
+        fn len(&self) -> u64 {
+            self.end - self.start
+        }
+    }
+
+    /// The offsets passed by the decoder are relative to the start of the
+    /// slice, so a slice which starts part-way through a file (eg. a member
+    /// of a tarball) is treated as a parquet file in its own right.
+    impl ChunkReader for FileSlice {
+        type T = FileSlice;
+
+        fn get_read(&self, start: u64) -> parquet::errors::Result<FileSlice> {
+            // The decoder expects a fresh reader; `slice` always puts the
+            // cursor at the beginning of the new slice
+            Ok(self.slice(start..))
+        }
+
+        fn get_bytes(&self, start: u64, length: usize) -> parquet::errors::Result<Bytes> {
+            let mut buf = vec![0; length];
+            let n = self
+                .slice(start..start.saturating_add(length as u64))
+                .read_full(&mut buf)?;
+            if n < length {
+                return Err(parquet::errors::ParquetError::EOF(format!(
+                    "Expected {length} bytes at offset {start}, but only got {n}"
+                )));
+            }
+            Ok(buf.into())
+        }
+    }
+
+    impl FileSlice {
+        /// Wrap the slice for reading as a parquet file
+        ///
+        /// See [`ParquetFileSlice`].
+        pub fn parquet_reader(&self) -> ParquetFileSlice {
+            ParquetFileSlice(self.clone_at_start())
+        }
+    }
+
+    /// A `FileSlice` which is read as a parquet file in its own right
+    ///
+    /// `FileSlice` already implements `ChunkReader`, and this wrapper behaves
+    /// identically: it exists so that the coordinate handling is pinned down
+    /// explicitly.  The offsets passed to `get_read` and `get_bytes` are
+    /// relative to the start of the slice, and `Length::len` is the length of
+    /// the slice, not of the underlying file.  So a slice which starts
+    /// part-way through a file (eg. a member of a tarball) is decoded just as
+    /// if it had been extracted to a file of its own.
+    #[derive(Clone, Debug)]
+    pub struct ParquetFileSlice(FileSlice);
+
+    impl ParquetFileSlice {
+        /// Get back the underlying `FileSlice`
+        pub fn into_inner(self) -> FileSlice {
+            self.0
+        }
+    }
+
+    impl Length for ParquetFileSlice {
+        fn len(&self) -> u64 {
+            Length::len(&self.0)
+        }
+    }
+
+    impl ChunkReader for ParquetFileSlice {
+        type T = FileSlice;
+
+        fn get_read(&self, start: u64) -> parquet::errors::Result<FileSlice> {
+            self.0.get_read(start)
+        }
+
+        fn get_bytes(&self, start: u64, length: usize) -> parquet::errors::Result<Bytes> {
+            self.0.get_bytes(start, length)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::tests::*;
+        use parquet::data_type::Int64Type;
+        use parquet::file::properties::WriterProperties;
+        use parquet::file::reader::FileReader;
+        use parquet::file::serialized_reader::SerializedFileReader;
+        use parquet::file::writer::SerializedFileWriter;
+        use parquet::record::RowAccessor;
+
+        /// A parquet file with a single `i64` column, with one row group for
+        /// each of `row_groups`
+        pub(crate) fn parquet_bytes(row_groups: &[&[i64]]) -> Vec<u8> {
+            let schema =
+                parquet::schema::parser::parse_message_type("message test { REQUIRED INT64 x; }")
+                    .unwrap();
+            let props = Arc::new(WriterProperties::builder().build());
+            let mut out = vec![];
+            let mut writer = SerializedFileWriter::new(&mut out, Arc::new(schema), props).unwrap();
+            for values in row_groups {
+                let mut row_group = writer.next_row_group().unwrap();
+                let mut col = row_group.next_column().unwrap().unwrap();
+                col.typed::<Int64Type>()
+                    .write_batch(values, None, None)
+                    .unwrap();
+                col.close().unwrap();
+                row_group.close().unwrap();
+            }
+            writer.close().unwrap();
+            out
+        }
+
+        /// Read every row group, returning the values in each
+        pub(crate) fn read_row_groups<R: ChunkReader + 'static>(reader: R) -> Vec<Vec<i64>> {
+            let reader = SerializedFileReader::new(reader).unwrap();
+            (0..reader.metadata().num_row_groups())
+                .map(|i| {
+                    reader
+                        .get_row_group(i)
+                        .unwrap()
+                        .get_row_iter(None)
+                        .unwrap()
+                        .map(|row| row.unwrap().get_long(0).unwrap())
+                        .collect()
+                })
+                .collect()
+        }
+
+        const ROW_GROUPS: &[&[i64]] = &[&[1, 2, 3], &[4, 5], &[6, 7, 8, 9]];
+
+        #[test]
+        fn whole_file() {
+            let tmp = TempFile::new(&parquet_bytes(ROW_GROUPS));
+            assert_eq!(read_row_groups(tmp.slice()), ROW_GROUPS);
+        }
+
+        #[cfg(feature = "tar")]
+        #[test]
+        fn member_of_tarball() {
+            let data = parquet_bytes(ROW_GROUPS);
+            let tmp = TempFile::new(&tarball(&[("readme", b"hello"), ("t.parquet", &data)]));
+            let archive = tar::Archive::new(tmp.open());
+            let (_, slice) = tar_entry(archive, Path::new("t.parquet")).unwrap().unwrap();
+            assert_ne!(slice.start_pos(), 0);
+            assert_eq!(read_row_groups(slice), ROW_GROUPS);
+        }
+
+        #[test]
+        fn sub_slice_via_parquet_reader() {
+            let data = parquet_bytes(ROW_GROUPS);
+            let mut contents = vec![0xff; 1000];
+            contents.extend_from_slice(&data);
+            contents.extend_from_slice(&[0xee; 1000]);
+            let tmp = TempFile::new(&contents);
+            let slice = tmp.slice().slice(1000..1000 + data.len() as u64);
+            let reader = slice.parquet_reader();
+            assert_eq!(Length::len(&reader), data.len() as u64);
+            assert_eq!(read_row_groups(reader.clone()), ROW_GROUPS);
+            // Offsets are relative to the slice
+            assert_eq!(&reader.get_bytes(0, 4).unwrap()[..], b"PAR1");
+            let mut magic = [0; 4];
+            reader
+                .get_read(data.len() as u64 - 4)
+                .unwrap()
+                .read_exact(&mut magic)
+                .unwrap();
+            assert_eq!(&magic, b"PAR1");
+            assert!(reader.get_bytes(data.len() as u64 - 2, 4).is_err());
+        }
+    }
+}
+
+#[cfg(feature = "parquet")]
+pub use parquet_impls::ParquetFileSlice;
+
+#[cfg(feature = "tar")]
+pub fn slice_tarball(
+    archive: tar::Archive<File>,
+) -> std::io::Result<impl Iterator<Item = (tar::Header, FileSlice)>> {
+    let (file, headers) = tarball_headers(archive)?;
+    Ok(headers
+        .into_iter()
+        .map(move |(header, start, end)| (header, file.slice(start..end))))
+}
+
+/// Like [`slice_tarball`], but yields the entries in order of their offset
+/// within the archive
+///
+/// Reading the resulting slices in order results in a sequential scan of the
+/// underlying file.  For a well-formed tarball this is the same as the order
+/// of the headers, since each member's data follows its header, so the sort
+/// is a no-op; the function exists so that callers which depend on a
+/// sequential scan can say so, rather than relying on that property of the
+/// archive.  Entries which start at the same offset keep their archive order.
+#[cfg(feature = "tar")]
+pub fn slice_tarball_sorted_by_offset(
+    archive: tar::Archive<File>,
+) -> std::io::Result<impl Iterator<Item = (tar::Header, FileSlice)>> {
+    let (file, mut headers) = tarball_headers(archive)?;
+    sort_by_offset(&mut headers);
+    Ok(headers
+        .into_iter()
+        .map(move |(header, start, end)| (header, file.slice(start..end))))
+}
+
+/// Find a single entry of a tarball by its path
+///
+/// This stops reading headers as soon as the entry is found, so it's cheaper
+/// than searching through the output of [`slice_tarball`].  Returns `None` if
+/// there's no such entry.
+#[cfg(feature = "tar")]
+pub fn tar_entry(
+    mut archive: tar::Archive<File>,
+    path: &Path,
+) -> std::io::Result<Option<(tar::Header, FileSlice)>> {
+    let mut found = None;
+    for entry in archive.entries_with_seek()? {
+        let entry = entry?;
+        if entry.path()? == path {
+            let start = entry.raw_file_position();
+            let end = start + entry.size();
+            found = Some((entry.header().clone(), start, end));
+            break;
+        }
+    }
+    let file = FileSlice::new(archive.into_inner());
+    Ok(found.map(|(header, start, end)| (header, file.slice(start..end))))
+}
+
+/// Sort `(header, start, end)` triples by their start offset
+#[cfg(feature = "tar")]
+fn sort_by_offset(headers: &mut [(tar::Header, u64, u64)]) {
+    headers.sort_by_key(|&(_, start, _)| start);
+}
+
+#[cfg(feature = "tar")]
+#[allow(clippy::type_complexity)]
+fn tarball_headers(
+    mut archive: tar::Archive<File>,
+) -> std::io::Result<(FileSlice, Vec<(tar::Header, u64, u64)>)> {
+    let headers = archive
+        .entries_with_seek()?
+        .map(move |entry| {
+            let entry = entry.unwrap();
+            let start = entry.raw_file_position();
+            let end = start + entry.size();
+            (entry.header().clone(), start, end)
+        })
+        .collect::<Vec<_>>();
+    Ok((FileSlice::new(archive.into_inner()), headers))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// A file in the temp dir which is deleted when dropped
+    pub(crate) struct TempFile {
+        path: PathBuf,
+    }
+
+    impl TempFile {
+        pub(crate) fn new(contents: &[u8]) -> TempFile {
+            static COUNTER: AtomicUsize = AtomicUsize::new(0);
+            let path = std::env::temp_dir().join(format!(
+                "fileslice-test-{}-{}",
+                std::process::id(),
+                COUNTER.fetch_add(1, Ordering::Relaxed),
+            ));
+            std::fs::write(&path, contents).unwrap();
+            TempFile { path }
+        }
+
+        pub(crate) fn path(&self) -> &Path {
+            &self.path
+        }
+
+        pub(crate) fn open(&self) -> File {
+            OpenOptions::new()
+                .read(true)
+                .write(true)
+                .open(&self.path)
+                .unwrap()
+        }
+
+        pub(crate) fn slice(&self) -> FileSlice {
+            FileSlice::new(self.open())
+        }
+
+        pub(crate) fn append(&self, data: &[u8]) {
+            let mut file = OpenOptions::new().append(true).open(&self.path).unwrap();
+            file.write_all(data).unwrap();
+        }
+
+        pub(crate) fn set_len(&self, len: u64) {
+            self.open().set_len(len).unwrap();
+        }
+
+        pub(crate) fn len(&self) -> u64 {
+            std::fs::metadata(&self.path).unwrap().len()
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+
+    std::thread_local! {
+        static PREAD_COUNT: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+    }
+
+    /// Called on every `pread`, so that tests can check how many syscalls
+    /// were made
+    pub(crate) fn count_pread() {
+        PREAD_COUNT.with(|c| c.set(c.get() + 1));
+    }
+
+    /// The number of `pread`s made by this thread while running `f`
+    pub(crate) fn preads_during(f: impl FnOnce()) -> usize {
+        let before = PREAD_COUNT.with(|c| c.get());
+        f();
+        PREAD_COUNT.with(|c| c.get()) - before
+    }
+
+    /// A tarball containing the given files, in order
+    #[cfg(feature = "tar")]
+    pub(crate) fn tarball(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(vec![]);
+        for (path, data) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, path, *data).unwrap();
+        }
+        builder.into_inner().unwrap()
+    }
+
+    /// `n` bytes of recognisable, non-repeating-ish data
+    pub(crate) fn fixture_bytes(n: usize) -> Vec<u8> {
+        (0..n).map(|i| (i % 251) as u8).collect()
+    }
+
+    /// A temp file containing `fixture_bytes(n)`
+    pub(crate) fn fixture(n: usize) -> TempFile {
+        TempFile::new(&fixture_bytes(n))
+    }
+
+    #[test]
+    fn read_to_end_with_overstated_len() {
+        let tmp = fixture(10);
+        let mut slice = FileSlice::with_file_len(tmp.open(), 1 << 34);
+        let mut buf = vec![];
+        assert_eq!(slice.read_to_end(&mut buf).unwrap(), 10);
+        assert_eq!(buf, fixture_bytes(10));
+        assert!(buf.capacity() < 1 << 24);
+    }
+
+    #[test]
+    fn read_to_end_appends_to_buf() {
+        let tmp = fixture(100);
+        let mut slice = tmp.slice().slice(10..20);
+        let mut buf = b"abc".to_vec();
+        assert_eq!(slice.read_to_end(&mut buf).unwrap(), 10);
+        assert_eq!(&buf[..3], b"abc");
+        assert_eq!(&buf[3..], &fixture_bytes(100)[10..20]);
+        assert_eq!(slice.read_to_end(&mut buf).unwrap(), 0);
+    }
+
+    #[test]
+    fn read_to_end_ignores_appended_data() {
+        let tmp = fixture(10);
+        let mut slice = tmp.slice();
+        tmp.append(&[0xff; 100]);
+        let mut buf = vec![];
+        assert_eq!(slice.read_to_end(&mut buf).unwrap(), 10);
+        assert_eq!(buf, fixture_bytes(10));
+    }
+
+    #[test]
+    fn read_to_end_while_appending() {
+        let len = 4 * 1024 * 1024;
+        let tmp = fixture(len);
+        let mut slice = tmp.slice();
+        let path = tmp.path().to_owned();
+        let writer = std::thread::spawn(move || {
+            let mut file = OpenOptions::new().append(true).open(path).unwrap();
+            for _ in 0..64 {
+                file.write_all(&[0xff; 4096]).unwrap();
+            }
+        });
+        let mut buf = vec![];
+        assert_eq!(slice.read_to_end(&mut buf).unwrap(), len);
+        writer.join().unwrap();
+        assert_eq!(buf, fixture_bytes(len));
+        assert_eq!(tmp.len(), len as u64 + 64 * 4096);
+    }
+
+    #[test]
+    fn truncate_file_to_sub_slice() {
+        let tmp = fixture(100);
+        let slice = tmp.slice().slice(20..60);
+        slice.truncate_file_to_slice(false).unwrap();
+        assert_eq!(tmp.len(), 60);
+        assert_eq!(
+            std::fs::read(tmp.path()).unwrap(),
+            &fixture_bytes(100)[..60]
+        );
+        // The slice itself is unaffected
+        assert_eq!(slice.read_range(..).unwrap(), &fixture_bytes(100)[20..60]);
+    }
+
+    #[test]
+    fn truncate_file_to_slice_punching_prefix() {
+        let tmp = fixture(3 * 4096);
+        let slice = tmp.slice().slice(4096..2 * 4096);
+        slice.truncate_file_to_slice(true).unwrap();
+        assert_eq!(tmp.len(), 2 * 4096);
+        let contents = std::fs::read(tmp.path()).unwrap();
+        assert_eq!(&contents[4096..], &fixture_bytes(3 * 4096)[4096..2 * 4096]);
+        // Whether the prefix was actually deallocated depends on the
+        // filesystem, but if it was, it reads back as zeros
+        let prefix = &contents[..4096];
+        assert!(prefix == &fixture_bytes(4096)[..] || prefix.iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    fn read_to_end_based_helpers_with_overstated_len() {
+        let tmp = fixture(10);
+        let slice = FileSlice::with_file_len(tmp.open(), 1 << 34);
+        assert_eq!(slice.read_block(0, 1 << 33).unwrap(), fixture_bytes(10));
+        let mut buf = vec![];
+        assert_eq!(slice.read_to_end_at(4, &mut buf).unwrap(), 6);
+        assert_eq!(buf, &fixture_bytes(10)[4..]);
+        assert_eq!(slice.read_all_into(&mut buf).unwrap(), 10);
+        assert_eq!(buf, fixture_bytes(10));
+    }
+
+    #[test]
+    fn read_exactly_to_end() {
+        let tmp = fixture(100);
+        let mut slice = tmp.slice().slice(10..20);
+        let mut buf = [0; 10];
+        assert_eq!(slice.read(&mut buf).unwrap(), 10);
+        assert_eq!(buf, fixture_bytes(100)[10..20]);
+        assert_eq!(slice.cursor_pos(), 20);
+        assert_eq!(slice.read(&mut buf).unwrap(), 0);
+        assert_eq!(slice.read(&mut []).unwrap(), 0);
+    }
+
+    #[test]
+    fn read_one_past_remaining() {
+        let tmp = fixture(100);
+        let mut slice = tmp.slice().slice(10..20);
+        slice.seek(SeekFrom::Start(3)).unwrap();
+        let mut buf = [0xaa; 8];
+        assert_eq!(slice.read(&mut buf).unwrap(), 7);
+        assert_eq!(buf[..7], fixture_bytes(100)[13..20]);
+        // Nothing beyond the end of the slice is written
+        assert_eq!(buf[7], 0xaa);
+        assert_eq!(slice.read(&mut buf).unwrap(), 0);
+    }
+
+    #[test]
+    fn read_past_end() {
+        let tmp = fixture(100);
+        let mut slice = tmp.slice().slice(10..20);
+        slice.seek(SeekFrom::Start(50)).unwrap();
+        assert_eq!(slice.read(&mut [0; 8]).unwrap(), 0);
+        slice.seek(SeekFrom::Start(u64::MAX - 10)).unwrap();
+        assert_eq!(slice.read(&mut [0; 8]).unwrap(), 0);
+    }
+
+    #[test]
+    fn slice_range_types() {
+        let tmp = fixture(100);
+        // A sub-slice, so that the offsets are relative to something non-zero
+        let base = tmp.slice().slice(10..90);
+        let data = &fixture_bytes(100)[10..90];
+        let check = |slice: FileSlice, expected: &[u8]| {
+            assert_eq!(slice.len(), expected.len());
+            assert_eq!(slice.read_range(..).unwrap(), expected);
+        };
+        check(base.slice(..), data);
+        check(base.slice(5..), &data[5..]);
+        check(base.slice(..20), &data[..20]);
+        check(base.slice(5..20), &data[5..20]);
+        check(base.slice(..=20), &data[..=20]);
+        check(base.slice(5..=20), &data[5..=20]);
+        check(
+            base.slice((Bound::Excluded(5), Bound::Included(20))),
+            &data[6..=20],
+        );
+        check(
+            base.slice((Bound::Excluded(5), Bound::Excluded(20))),
+            &data[6..20],
+        );
+        check(
+            base.slice((Bound::Included(5), Bound::Unbounded)),
+            &data[5..],
+        );
+        // Ranges reaching the end exactly, or beyond it, are clamped
+        check(base.slice(..=79), data);
+        check(base.slice(70..1000), &data[70..]);
+        check(base.slice(..=u64::MAX), data);
+        check(base.slice(u64::MAX..), &[]);
+        check(
+            base.slice((Bound::Excluded(u64::MAX), Bound::Unbounded)),
+            &[],
+        );
+        // Empty and inverted ranges give empty slices
+        check(base.slice(20..20), &[]);
+        check(base.slice((Bound::Included(5), Bound::Included(4))), &[]);
+        check(base.slice((Bound::Included(20), Bound::Excluded(5))), &[]);
+    }
+
+    #[test]
+    fn seek_extremes() {
+        let tmp = fixture(100);
+        let mut slice = tmp.slice().slice(10..20);
+        slice.seek(SeekFrom::Start(3)).unwrap();
+        // Errors leave the cursor where it was
+        assert!(slice.seek(SeekFrom::End(i64::MIN)).is_err());
+        assert_eq!(slice.cursor_pos(), 13);
+        assert!(slice.seek(SeekFrom::Current(i64::MIN)).is_err());
+        assert_eq!(slice.cursor_pos(), 13);
+        assert!(slice.seek(SeekFrom::Current(-4)).is_err());
+        assert_eq!(slice.cursor_pos(), 13);
+        // start + u64::MAX doesn't fit in a u64
+        assert!(slice.seek(SeekFrom::Start(u64::MAX)).is_err());
+        assert_eq!(slice.cursor_pos(), 13);
+
+        assert_eq!(slice.seek(SeekFrom::End(0)).unwrap(), 10);
+        assert_eq!(slice.cursor_pos(), 20);
+        assert_eq!(slice.read(&mut [0; 4]).unwrap(), 0);
+        assert_eq!(slice.seek(SeekFrom::End(-10)).unwrap(), 0);
+        assert!(slice.seek(SeekFrom::End(-11)).is_err());
+    }
+
+    #[test]
+    fn seek_start_u64_max_from_zero() {
+        let tmp = fixture(10);
+        let mut slice = tmp.slice();
+        assert_eq!(slice.seek(SeekFrom::Start(u64::MAX)).unwrap(), u64::MAX);
+        assert_eq!(slice.read(&mut [0; 4]).unwrap(), 0);
+        assert!(slice.seek(SeekFrom::Current(1)).is_err());
+        assert_eq!(slice.stream_position().unwrap(), u64::MAX);
+    }
+
+    #[test]
+    fn seek_current_past_end() {
+        let tmp = fixture(100);
+        let mut slice = tmp.slice().slice(10..20);
+        assert_eq!(slice.seek(SeekFrom::End(30)).unwrap(), 40);
+        assert_eq!(slice.seek(SeekFrom::Current(5)).unwrap(), 45);
+        assert_eq!(slice.seek(SeekFrom::Current(-43)).unwrap(), 2);
+        let mut buf = [0; 2];
+        slice.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, fixture_bytes(100)[12..14]);
+    }
+
+    #[test]
+    fn try_expand_preserves_cursor() {
+        let tmp = fixture(100);
+        let mut slice = tmp.slice().slice(10..20);
+        slice.seek(SeekFrom::Start(4)).unwrap();
+        tmp.append(&[0xff; 50]);
+        slice.try_expand().unwrap();
+        assert_eq!(slice.start_pos(), 0);
+        assert_eq!(slice.end_pos(), 150);
+        // Same absolute position; relative position shifted by the old start
+        assert_eq!(slice.cursor_pos(), 14);
+        assert_eq!(slice.stream_position().unwrap(), 14);
+        let mut buf = vec![];
+        slice.read_to_end(&mut buf).unwrap();
+        assert_eq!(&buf[..86], &fixture_bytes(100)[14..]);
+        assert_eq!(&buf[86..], &[0xff; 50]);
+    }
+
+    #[test]
+    fn read_uninit_vec_matches_read() {
+        let tmp = fixture(100_000);
+        let mut a = tmp.slice().slice(10..90_000);
+        let mut b = a.clone();
+        for len in [0, 1, 1000, 50_000] {
+            let mut expected = vec![0; len];
+            a.read_exact(&mut expected).unwrap();
+            assert_eq!(b.read_uninit_vec(len).unwrap(), expected);
+            assert_eq!(a.cursor_pos(), b.cursor_pos());
+        }
+    }
+
+    #[test]
+    fn read_uninit_vec_at_eof() {
+        let tmp = fixture(100);
+        let mut slice = tmp.slice().slice(10..20);
+        slice.seek(SeekFrom::Start(4)).unwrap();
+        assert_eq!(
+            slice.read_uninit_vec(100).unwrap(),
+            &fixture_bytes(100)[14..20]
+        );
+        assert_eq!(slice.cursor_pos(), 20);
+        assert!(slice.read_uninit_vec(100).unwrap().is_empty());
+        // The file is shorter than the slice claims
+        let mut slice = FileSlice::with_file_len(tmp.open(), 1000).slice(90..);
+        assert_eq!(
+            slice.read_uninit_vec(500).unwrap(),
+            &fixture_bytes(100)[90..]
+        );
+        assert_eq!(slice.cursor_pos(), 100);
+    }
+
+    #[test]
+    fn read_vectored_at_matches_separate_reads() {
+        let tmp = fixture(1000);
+        let slice = tmp.slice().slice(100..900);
+        let (mut header, mut body) = ([0; 16], [0; 100]);
+        let n = slice
+            .read_vectored_at(
+                5,
+                &mut [
+                    std::io::IoSliceMut::new(&mut header),
+                    std::io::IoSliceMut::new(&mut body),
+                ],
+            )
+            .unwrap();
+        assert_eq!(n, 116);
+        assert_eq!(header[..], slice.read_range(5..21).unwrap());
+        assert_eq!(body[..], slice.read_range(21..121).unwrap());
+    }
+
+    #[test]
+    fn read_vectored_at_stops_at_end() {
+        let tmp = fixture(1000);
+        let slice = tmp.slice().slice(100..900);
+        let (mut a, mut b, mut c) = ([0; 5], [0xaa; 10], [0xaa; 10]);
+        let n = slice
+            .read_vectored_at(
+                790,
+                &mut [
+                    std::io::IoSliceMut::new(&mut a),
+                    std::io::IoSliceMut::new(&mut b),
+                    std::io::IoSliceMut::new(&mut c),
+                ],
+            )
+            .unwrap();
+        assert_eq!(n, 10);
+        assert_eq!(a[..], fixture_bytes(1000)[890..895]);
+        assert_eq!(b[..5], fixture_bytes(1000)[895..900]);
+        assert_eq!(b[5..], [0xaa; 5]);
+        assert_eq!(c, [0xaa; 10]);
+        let n = slice
+            .read_vectored_at(800, &mut [std::io::IoSliceMut::new(&mut a)])
+            .unwrap();
+        assert_eq!(n, 0);
+    }
+
+    #[test]
+    fn read_vectored_at_many_buffers() {
+        let tmp = fixture(3000);
+        let slice = tmp.slice();
+        let mut bufs = vec![[0u8; 1]; 3000];
+        // There may be a limit on the number of buffers used per call, so
+        // keep going until they're all full
+        let mut done = 0;
+        while done < bufs.len() {
+            let mut slices = bufs[done..]
+                .iter_mut()
+                .map(|x| std::io::IoSliceMut::new(x))
+                .collect::<Vec<_>>();
+            let n = slice.read_vectored_at(done as u64, &mut slices).unwrap();
+            assert!(n > 0);
+            done += n;
+        }
+        assert_eq!(bufs.concat(), fixture_bytes(3000));
+    }
+
+    #[test]
+    fn aligned_slice_512() {
+        let tmp = fixture(2000);
+        let mut slice = tmp.slice().slice(700..1300);
+        slice.seek(SeekFrom::Start(50)).unwrap();
+        let aligned = slice.aligned_slice(512).unwrap();
+        assert_eq!((aligned.start_pos(), aligned.end_pos()), (512, 1536));
+        assert_eq!(aligned.cursor_pos(), 750);
+        assert_eq!(
+            aligned.read_range(..).unwrap(),
+            &fixture_bytes(2000)[512..1536]
+        );
+        // Already aligned
+        let aligned = tmp.slice().slice(512..1024).aligned_slice(512).unwrap();
+        assert_eq!((aligned.start_pos(), aligned.end_pos()), (512, 1024));
+        // The end isn't rounded up beyond the end of the file
+        let aligned = tmp.slice().slice(1600..1900).aligned_slice(512).unwrap();
+        assert_eq!((aligned.start_pos(), aligned.end_pos()), (1536, 2000));
+    }
+
+    #[test]
+    fn expand_by_after_append() {
+        let tmp = fixture(100);
+        let mut slice = tmp.slice().slice(10..100);
+        slice.seek(SeekFrom::Start(5)).unwrap();
+        tmp.append(&[0xff; 30]);
+        slice.expand_by(20).unwrap();
+        assert_eq!((slice.start_pos(), slice.end_pos()), (10, 120));
+        assert_eq!(slice.cursor_pos(), 15);
+        assert_eq!(slice.read_range(90..).unwrap(), [0xff; 20]);
+        // Capped at the end of the file
+        slice.expand_by(1000).unwrap();
+        assert_eq!(slice.end_pos(), 130);
+        slice.expand_by(u64::MAX).unwrap();
+        assert_eq!(slice.end_pos(), 130);
+    }
+
+    #[test]
+    fn expand_by_unchecked_after_append() {
+        let tmp = fixture(100);
+        let mut slice = tmp.slice();
+        tmp.append(&[0xff; 30]);
+        slice.expand_by_unchecked(30);
+        assert_eq!(slice.len(), 130);
+        let mut buf = vec![];
+        slice.read_to_end(&mut buf).unwrap();
+        assert_eq!(&buf[100..], [0xff; 30]);
+        // Trusting a bogus delta gives short reads, but nothing worse
+        slice.expand_by_unchecked(1 << 40);
+        slice.seek(SeekFrom::Start(0)).unwrap();
+        buf.clear();
+        assert_eq!(slice.read_to_end(&mut buf).unwrap(), 130);
+    }
+
+    #[test]
+    fn read_range_with_overstated_len() {
+        let tmp = fixture(10);
+        let slice = FileSlice::with_file_len(tmp.open(), 1 << 40);
+        let err = slice.read_range(5..).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+        assert_eq!(slice.read_range(2..8).unwrap(), &fixture_bytes(10)[2..8]);
+        let err = slice.read_last(1 << 39).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+        // No NUL, so this reads to the (claimed) end
+        let tmp = TempFile::new(b"abcdefghij");
+        let mut slice = FileSlice::with_file_len(tmp.open(), 1 << 40);
+        let err = slice.read_cstr().unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn windows_overlapping() {
+        let tmp = fixture(100);
+        let slice = tmp.slice().slice(50..60);
+        let bounds = |size, step| {
+            slice
+                .windows(size, step)
+                .map(|w| (w.start_pos() - 50, w.end_pos() - 50))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(bounds(4, 2), [(0, 4), (2, 6), (4, 8), (6, 10)]);
+        assert_eq!(bounds(4, 3), [(0, 4), (3, 7), (6, 10)]);
+        assert_eq!(bounds(3, 4), [(0, 3), (4, 7), (8, 10)]);
+        assert_eq!(bounds(10, 1), [(0, 10)]);
+        assert_eq!(bounds(100, 1), [(0, 10)]);
+        assert_eq!(bounds(1, u64::MAX), [(0, 1)]);
+        assert!(slice.slice(5..5).windows(4, 2).next().is_none());
+        let window = slice.windows(4, 2).nth(1).unwrap();
+        assert_eq!(window.read_range(..).unwrap(), &fixture_bytes(100)[52..56]);
+    }
+
+    #[test]
+    fn prefetch_is_transparent() {
+        let len = 1024 * 1024;
+        let tmp = fixture(len);
+        let mut slice = tmp.slice().slice(100..);
+        slice.seek(SeekFrom::Start(1000)).unwrap();
+        let started = std::time::Instant::now();
+        for _ in 0..10 {
+            slice.prefetch(u64::MAX);
+            slice.prefetch(4096);
+            slice.prefetch(0);
+        }
+        assert!(started.elapsed() < std::time::Duration::from_secs(5));
+        assert_eq!(slice.cursor_pos(), 1100);
+        let mut buf = vec![];
+        slice.read_to_end(&mut buf).unwrap();
+        assert_eq!(buf, &fixture_bytes(len)[1100..]);
+        // Prefetching from beyond the end is harmless
+        slice.prefetch(4096);
+        assert_eq!(slice.cursor_pos(), len as u64);
+    }
+
+    #[test]
+    fn bytes_iter_matches_read_to_end() {
+        let tmp = fixture(20_000);
+        let mut slice = tmp.slice().slice(100..19_000);
+        let mut expected = vec![];
+        slice.clone().read_to_end(&mut expected).unwrap();
+        // The cursor doesn't matter
+        slice.seek(SeekFrom::Start(500)).unwrap();
+        let mut got = vec![];
+        let preads = preads_during(|| {
+            got = slice
+                .bytes_iter()
+                .collect::<std::io::Result<Vec<u8>>>()
+                .unwrap()
+        });
+        assert_eq!(got, expected);
+        // One per 8 KiB block, rather than one per byte
+        assert!(preads <= 3, "{preads} preads");
+        assert_eq!(slice.cursor_pos(), 600);
+        assert_eq!(slice.slice(5..5).bytes_iter().count(), 0);
+    }
+
+    #[test]
+    fn read_last_bytes() {
+        let tmp = fixture(100);
+        let mut slice = tmp.slice().slice(10..90);
+        slice.seek(SeekFrom::Start(3)).unwrap();
+        let expected = &std::fs::read(tmp.path()).unwrap()[82..90];
+        assert_eq!(slice.read_last(8).unwrap(), expected);
+        assert_eq!(slice.read_last(1000).unwrap(), &fixture_bytes(100)[10..90]);
+        assert!(slice.read_last(0).unwrap().is_empty());
+        assert_eq!(slice.cursor_pos(), 13);
+    }
+
+    #[test]
+    fn clone_vs_clone_at_start() {
+        let tmp = fixture(100);
+        let mut slice = tmp.slice().slice(10..90);
+        slice.seek(SeekFrom::Start(30)).unwrap();
+        let mut cloned = slice.clone();
+        let mut fresh = slice.clone_at_start();
+        assert_eq!(cloned.cursor_pos(), 40);
+        assert_eq!(fresh.cursor_pos(), 10);
+        let mut buf = [0; 4];
+        cloned.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, fixture_bytes(100)[40..44]);
+        fresh.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, fixture_bytes(100)[10..14]);
+        // Neither affects the original
+        assert_eq!(slice.cursor_pos(), 40);
+        assert_eq!((fresh.start_pos(), fresh.end_pos()), (10, 90));
+    }
+
+    #[test]
+    fn truncate_at_first_delim() {
+        let tmp = TempFile::new(b"xxhello\0world\0yy");
+        let mut slice = tmp.slice().slice(2..14);
+        assert!(slice.truncate_at_first(0).unwrap());
+        assert_eq!(slice.read_range(..).unwrap(), b"hello");
+        // Already truncated, so there's no longer a delimiter
+        assert!(!slice.truncate_at_first(0).unwrap());
+        assert_eq!(slice.read_range(..).unwrap(), b"hello");
+        // Only the slice is searched
+        let mut slice = tmp.slice().slice(..5);
+        assert!(!slice.truncate_at_first(0).unwrap());
+        assert_eq!(slice.read_range(..).unwrap(), b"xxhel");
+        // Delimiter at the very start
+        let mut slice = tmp.slice().slice(7..);
+        assert!(slice.truncate_at_first(0).unwrap());
+        assert!(slice.is_empty());
+    }
+
+    #[test]
+    fn verify_len_after_truncation() {
+        let tmp = fixture(100);
+        let slice = tmp.slice().slice(10..50);
+        assert!(slice.verify_len().unwrap());
+        tmp.set_len(60);
+        assert!(slice.verify_len().unwrap());
+        tmp.set_len(50);
+        assert!(slice.verify_len().unwrap());
+        tmp.set_len(49);
+        assert!(!slice.verify_len().unwrap());
+        // It doesn't modify the slice
+        assert_eq!(slice.len(), 40);
+    }
+
+    #[test]
+    fn parts_round_trip() {
+        let tmp = fixture(100);
+        let mut slice = tmp.slice().slice(10..90);
+        slice.seek(SeekFrom::Start(5)).unwrap();
+        let (file, start, end, cursor) = slice.clone().into_parts();
+        assert_eq!((start, end, cursor), (10, 90, 15));
+        let mut rebuilt = FileSlice::from_parts(file.clone(), start, end, cursor);
+        assert!(rebuilt.is_same_file(&slice));
+        assert_eq!(rebuilt.key(), slice.key());
+        assert_eq!(rebuilt.cursor_pos(), 15);
+        let mut buf = vec![];
+        rebuilt.read_to_end(&mut buf).unwrap();
+        assert_eq!(buf, &fixture_bytes(100)[15..90]);
+
+        let checked = FileSlice::from_parts_checked(file.clone(), start, end, cursor).unwrap();
+        assert_eq!(checked.cursor_pos(), 15);
+        let kind = |r: std::io::Result<FileSlice>| r.unwrap_err().kind();
+        let invalid = std::io::ErrorKind::InvalidInput;
+        assert_eq!(
+            kind(FileSlice::from_parts_checked(file.clone(), 20, 10, 20)),
+            invalid
+        );
+        assert_eq!(
+            kind(FileSlice::from_parts_checked(file.clone(), 10, 20, 5)),
+            invalid
+        );
+        assert_eq!(
+            kind(FileSlice::from_parts_checked(file, 10, 101, 10)),
+            invalid
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "cursor (5) is before start (10)")]
+    fn from_parts_cursor_before_start() {
+        let tmp = fixture(100);
+        let (file, ..) = tmp.slice().into_parts();
+        FileSlice::from_parts(file, 10, 20, 5);
+    }
+
+    #[test]
+    #[should_panic(expected = "start (20) is beyond end (10)")]
+    fn from_parts_inverted() {
+        let tmp = fixture(100);
+        let (file, ..) = tmp.slice().into_parts();
+        FileSlice::from_parts(file, 20, 10, 20);
+    }
+
+    #[test]
+    fn read_exact_too_short_does_no_io() {
+        let tmp = fixture(100);
+        let mut slice = tmp.slice().slice(10..20);
+        slice.seek(SeekFrom::Start(4)).unwrap();
+        let mut buf = [0xaa; 7];
+        let mut result = Ok(());
+        assert_eq!(preads_during(|| result = slice.read_exact(&mut buf)), 0);
+        assert_eq!(
+            result.unwrap_err().kind(),
+            std::io::ErrorKind::UnexpectedEof
+        );
+        assert_eq!(buf, [0xaa; 7]);
+        assert_eq!(slice.cursor_pos(), 14);
+    }
+
+    #[test]
+    fn read_exact_happy_path() {
+        let tmp = fixture(100);
+        let mut slice = tmp.slice().slice(10..20);
+        let mut buf = [0; 6];
+        assert_eq!(preads_during(|| slice.read_exact(&mut buf).unwrap()), 1);
+        assert_eq!(buf, fixture_bytes(100)[10..16]);
+        let mut buf = [0; 4];
+        slice.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, fixture_bytes(100)[16..20]);
+        slice.read_exact(&mut []).unwrap();
+        assert!(slice.read_exact(&mut [0]).is_err());
+    }
+
+    #[test]
+    fn split_at_checked_bounds() {
+        let tmp = fixture(100);
+        let slice = tmp.slice().slice(10..20);
+        let bounds = |(a, b): (FileSlice, FileSlice)| {
+            (a.start_pos(), a.end_pos(), b.start_pos(), b.end_pos())
+        };
+        assert_eq!(bounds(slice.split_at_checked(4).unwrap()), (10, 14, 14, 20));
+        assert_eq!(bounds(slice.split_at_checked(0).unwrap()), (10, 10, 10, 20));
+        assert_eq!(
+            bounds(slice.split_at_checked(10).unwrap()),
+            (10, 20, 20, 20)
+        );
+        assert!(slice.split_at_checked(11).is_none());
+        assert!(slice.split_at_checked(u64::MAX).is_none());
+        // Unlike split_at, which clamps
+        assert_eq!(bounds(slice.split_at(11)), (10, 20, 20, 20));
+    }
+
+    #[test]
+    fn read_to_end_at_offset() {
+        let tmp = fixture(100);
+        let mut slice = tmp.slice().slice(40..70);
+        slice.seek(SeekFrom::Start(25)).unwrap();
+        let mut out = vec![];
+        assert_eq!(slice.read_to_end_at(10, &mut out).unwrap(), 20);
+        assert_eq!(out, &fixture_bytes(100)[50..70]);
+        // Appends, and doesn't touch the cursor
+        assert_eq!(slice.read_to_end_at(28, &mut out).unwrap(), 2);
+        assert_eq!(&out[20..], &fixture_bytes(100)[68..70]);
+        assert_eq!(slice.read_to_end_at(30, &mut out).unwrap(), 0);
+        assert_eq!(slice.read_to_end_at(u64::MAX, &mut out).unwrap(), 0);
+        assert_eq!(slice.cursor_pos(), 65);
+    }
+
+    #[test]
+    fn with_file_len_matches_new() {
+        let tmp = fixture(100);
+        let a = tmp.slice();
+        let b = FileSlice::with_file_len(tmp.open(), 100);
+        assert_eq!(a.len(), b.len());
+        assert_eq!(a.key(), b.key());
+        let (mut x, mut y) = (vec![], vec![]);
+        a.slice(10..).read_to_end(&mut x).unwrap();
+        b.slice(10..).read_to_end(&mut y).unwrap();
+        assert_eq!(x, y);
+        // An understated length limits the slice
+        let c = FileSlice::with_file_len(tmp.open(), 50);
+        assert_eq!(c.read_range(..).unwrap(), &fixture_bytes(100)[..50]);
+    }
+
+    #[cfg(feature = "tar")]
+    #[test]
+    fn tarball_sorted_by_offset() {
+        let tmp = TempFile::new(&tarball(&[
+            ("b", b"second"),
+            ("a", &fixture_bytes(1000)),
+            ("c", b""),
+            ("d", b"fourth"),
+        ]));
+        let unsorted = slice_tarball(tar::Archive::new(tmp.open()))
+            .unwrap()
+            .map(|(h, s)| (h.path().unwrap().into_owned(), s))
+            .collect::<Vec<_>>();
+        let sorted = slice_tarball_sorted_by_offset(tar::Archive::new(tmp.open()))
+            .unwrap()
+            .map(|(h, s)| (h.path().unwrap().into_owned(), s))
+            .collect::<Vec<_>>();
+        // A member's data always follows its header, so the archive order
+        // is already the offset order
+        let paths = sorted
+            .iter()
+            .map(|(p, _)| p.to_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(paths, ["b", "a", "c", "d"]);
+        assert!(sorted
+            .windows(2)
+            .all(|w| w[0].1.start_pos() < w[1].1.start_pos()));
+        for ((p1, s1), (p2, s2)) in unsorted.iter().zip(&sorted) {
+            assert_eq!(p1, p2);
+            assert_eq!(s1.key(), s2.key());
+        }
+        assert_eq!(sorted[1].1.read_range(..).unwrap(), fixture_bytes(1000));
+        assert_eq!(sorted[3].1.read_range(..).unwrap(), b"fourth");
+        assert!(sorted[2].1.is_empty());
+    }
+
+    #[test]
+    fn is_same_file_cases() {
+        let tmp = fixture(100);
+        let other = fixture(100);
+        let slice = tmp.slice();
+        assert!(slice.is_same_file(&slice.clone()));
+        assert!(slice.is_same_file(&slice.slice(10..20)));
+        assert!(slice.slice(10..20).is_same_file(&slice.slice(50..)));
+        // Opening the same path again gives a different open file
+        assert!(!slice.is_same_file(&tmp.slice()));
+        assert!(!slice.is_same_file(&other.slice()));
+    }
+
+    #[test]
+    fn seek_fraction_values() {
+        let tmp = fixture(100);
+        let mut slice = tmp.slice().slice(10..90);
+        assert_eq!(slice.seek_fraction(0.5), 40);
+        assert_eq!(slice.cursor_pos(), 50);
+        assert_eq!(slice.seek_fraction(0.0), 0);
+        assert_eq!(slice.cursor_pos(), 10);
+        assert_eq!(slice.seek_fraction(1.0), 80);
+        assert_eq!(slice.cursor_pos(), 90);
+        assert_eq!(slice.seek_fraction(-3.0), 0);
+        assert_eq!(slice.seek_fraction(7.5), 80);
+        assert_eq!(slice.seek_fraction(f64::INFINITY), 80);
+        assert_eq!(slice.seek_fraction(f64::NAN), 0);
+        assert_eq!(slice.slice(5..5).seek_fraction(0.5), 0);
+    }
+
+    #[test]
+    fn read_range_bounds() {
+        let tmp = fixture(100);
+        let mut slice = tmp.slice().slice(10..90);
+        slice.seek(SeekFrom::Start(7)).unwrap();
+        assert_eq!(
+            slice.read_range(5..15).unwrap(),
+            &fixture_bytes(100)[15..25]
+        );
+        assert_eq!(slice.read_range(..=2).unwrap(), &fixture_bytes(100)[10..13]);
+        assert_eq!(slice.read_range(70..).unwrap(), &fixture_bytes(100)[80..90]);
+        assert!(slice.read_range(80..).unwrap().is_empty());
+        assert_eq!(slice.cursor_pos(), 17);
+        let kind = |r: std::io::Result<Vec<u8>>| r.unwrap_err().kind();
+        assert_eq!(
+            kind(slice.read_range(70..81)),
+            std::io::ErrorKind::UnexpectedEof
+        );
+        assert_eq!(
+            kind(slice.read_range(..=80)),
+            std::io::ErrorKind::UnexpectedEof
+        );
+        assert_eq!(
+            kind(slice.read_range((Bound::Included(20), Bound::Excluded(10)))),
+            std::io::ErrorKind::InvalidInput
+        );
+    }
+
+    #[test]
+    fn position_clamped_past_end() {
+        let tmp = fixture(100);
+        let mut slice = tmp.slice().slice(10..20);
+        slice.seek(SeekFrom::Start(4)).unwrap();
+        assert_eq!(slice.stream_position().unwrap(), 4);
+        assert_eq!(slice.position_clamped(), 4);
+        slice.seek(SeekFrom::End(25)).unwrap();
+        assert_eq!(slice.stream_position().unwrap(), 35);
+        assert_eq!(slice.position_clamped(), 10);
+    }
+
+    #[test]
+    fn advise_dontneed_ok() {
+        let tmp = fixture(100_000);
+        let mut slice = tmp.slice().slice(1000..90_000);
+        let mut buf = vec![];
+        slice.read_to_end(&mut buf).unwrap();
+        slice.advise_dontneed().unwrap();
+        slice.slice(5..5).advise_dontneed().unwrap();
+        assert_eq!(slice.read_range(..).unwrap(), buf);
+    }
+
+    #[test]
+    fn read_cstr_strings() {
+        let tmp = TempFile::new(b"xhello\0\0world\0tail");
+        let mut slice = tmp.slice().slice(1..);
+        assert_eq!(slice.read_cstr().unwrap(), b"hello");
+        assert_eq!(slice.cursor_pos(), 7);
+        assert_eq!(slice.read_cstr().unwrap(), b"");
+        assert_eq!(slice.read_cstr().unwrap(), b"world");
+        // Terminated by the end of the slice
+        assert_eq!(slice.read_cstr().unwrap(), b"tail");
+        assert_eq!(slice.cursor_pos(), 18);
+        assert_eq!(slice.read_cstr().unwrap(), b"");
+        // The NUL beyond the end of a sub-slice isn't seen
+        let mut slice = tmp.slice().slice(8..11);
+        assert_eq!(slice.read_cstr().unwrap(), b"wor");
+    }
+
+    #[test]
+    fn new_range_matches_slice() {
+        let tmp = fixture(100);
+        let a = FileSlice::new_range(tmp.open(), 10..20);
+        let b = FileSlice::new(tmp.open()).slice(10..20);
+        assert_eq!(a.key(), b.key());
+        assert_eq!(a.cursor_pos(), b.cursor_pos());
+        assert_eq!(a.read_range(..).unwrap(), b.read_range(..).unwrap());
+        // Clamped to the length of the file
+        let a = FileSlice::new_range(tmp.open(), 90..200);
+        assert_eq!((a.start_pos(), a.end_pos()), (90, 100));
+        let a = FileSlice::new_range(tmp.open(), 150..);
+        assert!(a.is_empty());
+    }
+
+    #[test]
+    fn checked_slice_errors() {
+        let tmp = fixture(100);
+        let slice = tmp.slice().slice(10..20);
+        let ok = slice.checked_slice(2..5).unwrap();
+        assert_eq!((ok.start_pos(), ok.end_pos()), (12, 15));
+        let ok = slice.checked_slice(..=9).unwrap();
+        assert_eq!((ok.start_pos(), ok.end_pos()), (10, 20));
+        assert!(slice.checked_slice(10..).unwrap().is_empty());
+        let err = |r: Result<FileSlice, SliceError>| r.unwrap_err();
+        assert_eq!(
+            err(slice.checked_slice((Bound::Included(5), Bound::Excluded(2)))),
+            SliceError::Inverted
+        );
+        assert_eq!(err(slice.checked_slice(11..)), SliceError::Inverted);
+        assert_eq!(err(slice.checked_slice(5..11)), SliceError::OutOfBounds);
+        assert_eq!(err(slice.checked_slice(..=10)), SliceError::OutOfBounds);
+        assert_eq!(
+            err(slice.checked_slice(..=u64::MAX)),
+            SliceError::OutOfBounds
+        );
+    }
+
+    #[test]
+    fn csv_record_offsets_quoting() {
+        let csv = b"a,b,c\n\"multi\nline\",\"x,y\",z\n\"say \"\"hi\n\"\"\",2,3\r\nlast,row,\"\"\n";
+        let tmp = TempFile::new(csv);
+        let slice = tmp.slice();
+        let offsets = slice.csv_record_offsets(b',', b'"').unwrap();
+        assert_eq!(offsets, [0, 6, 27, 46]);
+        let records = offsets
+            .iter()
+            .zip(offsets[1..].iter().chain([&(csv.len() as u64)]))
+            .map(|(&a, &b)| slice.read_range(a..b).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(records[1], b"\"multi\nline\",\"x,y\",z\n");
+        assert_eq!(records[2], b"\"say \"\"hi\n\"\"\",2,3\r\n");
+        assert_eq!(records[3], b"last,row,\"\"\n");
+    }
+
+    #[test]
+    fn csv_record_offsets_edge_cases() {
+        let tmp = TempFile::new(b"x\"y,1\nno,newline");
+        // A quote in the middle of an unquoted field is literal
+        assert_eq!(tmp.slice().csv_record_offsets(b',', b'"').unwrap(), [0, 6]);
+        // Other delimiters, and relative to a sub-slice
+        let tmp = TempFile::new(b"junk'a;\n';b\nc");
+        let slice = tmp.slice().slice(4..);
+        assert_eq!(slice.csv_record_offsets(b';', b'\'').unwrap(), [0, 8]);
+        assert!(tmp
+            .slice()
+            .slice(3..3)
+            .csv_record_offsets(b',', b'"')
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn open_with_options() {
+        let tmp = fixture(100);
+        let mut opts = OpenOptions::new();
+        opts.read(true).write(true);
+        let slice = FileSlice::open_with(tmp.path(), &opts, 10..20).unwrap();
+        assert_eq!(slice.read_range(..).unwrap(), &fixture_bytes(100)[10..20]);
+        // The file really was opened for writing
+        slice.truncate_file_to_slice(false).unwrap();
+        assert_eq!(tmp.len(), 20);
+        // Errors from opening are passed through
+        let missing = tmp.path().with_extension("missing");
+        let err = FileSlice::open_with(missing, &opts, ..).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn remaining_slice_after_header() {
+        let tmp = fixture(100);
+        let mut slice = tmp.slice().slice(10..50);
+        slice.read_exact(&mut [0; 10]).unwrap();
+        let mut rest = slice.remaining_slice();
+        assert_eq!(rest.len(), 30);
+        assert_eq!((rest.start_pos(), rest.cursor_pos()), (20, 20));
+        let mut buf = vec![];
+        rest.read_to_end(&mut buf).unwrap();
+        assert_eq!(buf, &fixture_bytes(100)[20..50]);
+        // Past the end
+        slice.seek(SeekFrom::End(10)).unwrap();
+        assert!(slice.remaining_slice().is_empty());
+    }
+
+    #[test]
+    fn cursor_pos_and_relative() {
+        let tmp = fixture(100);
+        let mut slice = tmp.slice().slice(30..60);
+        assert_eq!((slice.cursor_pos(), slice.cursor_relative()), (30, 0));
+        slice.seek(SeekFrom::Start(12)).unwrap();
+        assert_eq!(slice.cursor_pos(), 42);
+        assert_eq!(slice.cursor_relative(), 12);
+        assert_eq!(
+            slice.cursor_pos() - slice.cursor_relative(),
+            slice.start_pos()
+        );
+        assert_eq!(slice.stream_position().unwrap(), slice.cursor_relative());
+    }
+
+    #[test]
+    fn limited_is_seekable() {
+        let tmp = fixture(100);
+        let mut slice = tmp.slice().slice(10..90);
+        slice.read_exact(&mut [0; 5]).unwrap();
+        let mut limited = slice.limited(20);
+        assert_eq!(limited.len(), 20);
+        assert_eq!(limited.seek(SeekFrom::End(0)).unwrap(), 20);
+        assert_eq!(limited.seek(SeekFrom::End(-4)).unwrap(), 16);
+        let mut buf = vec![];
+        limited.read_to_end(&mut buf).unwrap();
+        assert_eq!(buf, &fixture_bytes(100)[31..35]);
+        limited.seek(SeekFrom::Start(2)).unwrap();
+        let mut buf = [0; 3];
+        limited.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, fixture_bytes(100)[17..20]);
+        // Clamped to the end of the parent, which is unaffected
+        assert_eq!(slice.limited(1000).len(), 75);
+        assert_eq!(slice.cursor_pos(), 15);
+    }
+
+    #[test]
+    fn read_at_least_min() {
+        let tmp = fixture(100);
+        let mut slice = tmp.slice().slice(10..30);
+        let mut buf = [0; 8];
+        // May read more than `min`, up to the size of the buffer
+        let n = slice.read_at_least(&mut buf, 3).unwrap();
+        assert!((3..=8).contains(&n));
+        assert_eq!(buf[..n], fixture_bytes(100)[10..10 + n]);
+        slice.seek(SeekFrom::Start(15)).unwrap();
+        assert_eq!(slice.read_at_least(&mut buf, 5).unwrap(), 5);
+        assert_eq!(buf[..5], fixture_bytes(100)[25..30]);
+        assert_eq!(slice.read_at_least(&mut buf, 0).unwrap(), 0);
+    }
+
+    #[test]
+    fn read_at_least_eof() {
+        let tmp = fixture(100);
+        let mut slice = tmp.slice().slice(10..30);
+        slice.seek(SeekFrom::Start(16)).unwrap();
+        let err = slice.read_at_least(&mut [0; 8], 5).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn read_fast_and_slow_paths_agree() {
+        let tmp = fixture(10_000);
+        let slice = tmp.slice().slice(100..9_000);
+        // Fast path: every buffer fits within the slice
+        let mut fast = slice.clone();
+        let mut a = vec![];
+        let mut buf = [0; 100];
+        loop {
+            let n = fast
+                .read(&mut buf[..fast.bytes_remaining().min(100)])
+                .unwrap();
+            if n == 0 {
+                break;
+            }
+            a.extend_from_slice(&buf[..n]);
+        }
+        // Slow path: the buffer always extends beyond the end
+        let mut slow = slice.clone();
+        let mut b = vec![];
+        let mut buf = vec![0; 10_000];
+        loop {
+            let n = slow.read(&mut buf).unwrap();
+            if n == 0 {
+                break;
+            }
+            b.extend_from_slice(&buf[..n]);
+        }
+        assert_eq!(a, b);
+        assert_eq!(a, &fixture_bytes(10_000)[100..9_000]);
+    }
+
+    #[test]
+    fn into_bufread_lines() {
+        use std::io::BufRead;
+        let tmp = TempFile::new(b"skip\none\ntwo\nthree\nskip");
+        let slice = tmp.slice().slice(5..19);
+        let lines = slice
+            .clone()
+            .into_bufread(4)
+            .lines()
+            .collect::<std::io::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(lines, ["one", "two", "three"]);
+        let mut rdr = slice.into_bufread_default();
+        assert_eq!(rdr.capacity(), 64 * 1024);
+        let mut line = String::new();
+        rdr.read_line(&mut line).unwrap();
+        assert_eq!(line, "one\n");
+    }
+
+    #[test]
+    fn detach_outlives_original() {
+        let tmp = fixture(100);
+        let mut slice = tmp.slice().slice(10..50);
+        slice.seek(SeekFrom::Start(5)).unwrap();
+        let mut detached = slice.detach().unwrap();
+        assert!(!detached.is_same_file(&slice));
+        assert_eq!(detached.key(), slice.key());
+        assert_eq!(detached.cursor_pos(), 15);
+        // The original is the sole owner of its `File` again
+        let file = slice.try_unwrap().unwrap();
+        drop(file);
+        let mut buf = vec![];
+        detached.read_to_end(&mut buf).unwrap();
+        assert_eq!(buf, &fixture_bytes(100)[15..50]);
+    }
+
+    #[test]
+    fn slice_keys_in_btreemap() {
+        let tmp = fixture(100);
+        let slice = tmp.slice();
+        let mut map = std::collections::BTreeMap::new();
+        for range in [50..60, 10..20, 10..15, 90..100, 0..100] {
+            let s = slice.slice(range.clone());
+            map.insert(s.key(), range);
+        }
+        let keys = map.keys().copied().collect::<Vec<_>>();
+        assert_eq!(
+            keys,
+            [
+                SliceKey(0, 100),
+                SliceKey(10, 15),
+                SliceKey(10, 20),
+                SliceKey(50, 60),
+                SliceKey(90, 100),
+            ]
+        );
+        // Keys are absolute, so nested slices get the same key as direct ones
+        assert_eq!(slice.slice(40..80).slice(10..20).key(), SliceKey(50, 60));
+        let mut set = std::collections::HashSet::new();
+        assert!(set.insert(slice.slice(1..2).key()));
+        assert!(!set.insert(slice.clone().slice(1..2).key()));
+    }
+
+    #[test]
+    fn read_exact_or_eof_records() {
+        let tmp = fixture(100);
+        let mut slice = tmp.slice().slice(10..22);
+        let mut records = vec![];
+        let mut buf = [0; 4];
+        while slice.read_exact_or_eof(&mut buf).unwrap().is_some() {
+            records.push(buf);
+        }
+        assert_eq!(records.concat(), &fixture_bytes(100)[10..22]);
+        assert_eq!(slice.read_exact_or_eof(&mut buf).unwrap(), None);
+
+        // A partial record at the end is an error
+        let mut slice = tmp.slice().slice(10..20);
+        slice.read_exact_or_eof(&mut buf).unwrap().unwrap();
+        slice.read_exact_or_eof(&mut buf).unwrap().unwrap();
+        let err = slice.read_exact_or_eof(&mut buf).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn slice_from_cursor_body() {
+        let mut data = vec![0xee; 3];
+        data.extend_from_slice(&5u32.to_le_bytes());
+        data.extend_from_slice(b"hello, and more");
+        let tmp = TempFile::new(&data);
+        let mut slice = tmp.slice().slice(3..);
+        let mut len = [0; 4];
+        slice.read_exact(&mut len).unwrap();
+        let body = slice.slice_from_cursor(u32::from_le_bytes(len).into());
+        assert_eq!(body.read_range(..).unwrap(), b"hello");
+        assert_eq!(body.cursor_pos(), body.start_pos());
+        assert_eq!(slice.cursor_pos(), 7);
+        // Clamped to the end
+        assert_eq!(slice.slice_from_cursor(1000).len(), 15);
+    }
+
+    #[test]
+    fn set_readahead_ok() {
+        let tmp = fixture(100_000);
+        let mut slice = tmp.slice().slice(100..);
+        slice.seek(SeekFrom::Start(1000)).unwrap();
+        slice.set_readahead(64 * 1024).unwrap();
+        slice.set_readahead(u64::MAX).unwrap();
+        slice.set_readahead(0).unwrap();
+        assert_eq!(slice.cursor_pos(), 1100);
+        let mut buf = vec![];
+        slice.read_to_end(&mut buf).unwrap();
+        assert_eq!(buf, &fixture_bytes(100_000)[1100..]);
+        // At the end there's nothing to read ahead
+        slice.set_readahead(4096).unwrap();
+    }
+
+    #[test]
+    fn truncate_to_cursor_prefix() {
+        let tmp = fixture(100);
+        let mut slice = tmp.slice().slice(10..50);
+        slice.read_exact(&mut [0; 12]).unwrap();
+        slice.truncate_to_cursor();
+        assert_eq!(slice.len(), 12);
+        assert_eq!(slice.read_range(..).unwrap(), &fixture_bytes(100)[10..22]);
+        // A cursor beyond the end leaves the slice alone
+        slice.seek(SeekFrom::End(10)).unwrap();
+        slice.truncate_to_cursor();
+        assert_eq!(slice.len(), 12);
+    }
+
+    #[test]
+    fn seek_relative_offsets() {
+        let tmp = fixture(100);
+        let mut slice = tmp.slice().slice(10..50);
+        slice.seek_relative(15).unwrap();
+        assert_eq!(slice.cursor_relative(), 15);
+        slice.seek_relative(-5).unwrap();
+        assert_eq!(slice.cursor_relative(), 10);
+        let mut buf = [0; 2];
+        slice.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, fixture_bytes(100)[20..22]);
+        slice.seek_relative(-12).unwrap();
+        assert_eq!(slice.cursor_relative(), 0);
+        assert!(slice.seek_relative(-1).is_err());
+        assert_eq!(slice.cursor_relative(), 0);
+        // Beyond the end is allowed
+        slice.seek_relative(100).unwrap();
+        assert_eq!(slice.cursor_relative(), 100);
+    }
+
+    #[cfg(feature = "tar")]
+    #[test]
+    fn tar_entry_by_path() {
+        let tmp = TempFile::new(&tarball(&[
+            ("a.txt", b"first"),
+            ("dir/b.bin", &fixture_bytes(1000)),
+            ("c.txt", b"third"),
+        ]));
+        let (header, slice) = tar_entry(tar::Archive::new(tmp.open()), Path::new("dir/b.bin"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(header.size().unwrap(), 1000);
+        assert_eq!(slice.read_range(..).unwrap(), fixture_bytes(1000));
+        let (_, slice) = tar_entry(tar::Archive::new(tmp.open()), Path::new("c.txt"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(slice.read_range(..).unwrap(), b"third");
+        assert!(tar_entry(tar::Archive::new(tmp.open()), Path::new("nope"))
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn read_block_interior_and_last() {
+        let tmp = fixture(100);
+        let mut slice = tmp.slice().slice(10..60);
+        slice.seek(SeekFrom::Start(3)).unwrap();
+        assert_eq!(
+            slice.read_block(1, 16).unwrap(),
+            &fixture_bytes(100)[26..42]
+        );
+        assert_eq!(
+            slice.read_block(3, 16).unwrap(),
+            &fixture_bytes(100)[58..60]
+        );
+        assert!(slice.read_block(4, 16).unwrap().is_empty());
+        assert!(slice.read_block(u64::MAX, u64::MAX).unwrap().is_empty());
+        assert!(slice.read_block(0, 0).unwrap().is_empty());
+        assert_eq!(slice.cursor_pos(), 13);
+    }
+
+    #[test]
+    fn advise_random_ok() {
+        let tmp = fixture(100_000);
+        let slice = tmp.slice().slice(1000..);
+        slice.advise_random().unwrap();
+        slice.advise_range(0..4096, Advice::WillNeed).unwrap();
+        slice
+            .advise_range(50_000..u64::MAX, Advice::Normal)
+            .unwrap();
+        assert_eq!(
+            slice.read_range(..).unwrap(),
+            &fixture_bytes(100_000)[1000..]
+        );
+    }
+
+    #[test]
+    fn from_parts_checked_over_long() {
+        let tmp = fixture(100);
+        let (file, ..) = tmp.slice().into_parts();
+        let err = FileSlice::from_parts_checked(file.clone(), 50, 101, 50).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        // Exactly reaching the end is fine
+        let slice = FileSlice::from_parts_checked(file.clone(), 50, 100, 50).unwrap();
+        assert_eq!(slice.len(), 50);
+        // The unchecked version takes it on trust
+        assert_eq!(FileSlice::from_parts(file, 50, 101, 50).len(), 51);
+    }
+
+    #[test]
+    fn count_newlines() {
+        // Enough lines to span several blocks
+        let text = (0..5000).map(|i| format!("line {i}\n")).collect::<String>();
+        let tmp = TempFile::new(text.as_bytes());
+        let mut slice = tmp.slice();
+        slice.seek(SeekFrom::Start(100)).unwrap();
+        assert_eq!(slice.count_occurrences(b'\n').unwrap(), 5000);
+        assert_eq!(slice.slice(..7).count_occurrences(b'\n').unwrap(), 1);
+        assert_eq!(slice.slice(1..6).count_occurrences(b'\n').unwrap(), 0);
+        assert_eq!(slice.count_occurrences(b'#').unwrap(), 0);
+    }
+
+    #[test]
+    fn peek_byte_cases() {
+        let tmp = TempFile::new(b"abc");
+        let mut slice = tmp.slice().slice(1..);
+        assert_eq!(slice.peek_byte().unwrap(), Some(b'b'));
+        assert_eq!(slice.peek_byte().unwrap(), Some(b'b'));
+        assert_eq!(slice.cursor_pos(), 1);
+        slice.read_exact(&mut [0; 2]).unwrap();
+        assert_eq!(slice.peek_byte().unwrap(), None);
+        slice.seek(SeekFrom::End(5)).unwrap();
+        assert_eq!(slice.peek_byte().unwrap(), None);
+        // The end of a sub-slice counts as the end
+        assert_eq!(tmp.slice().slice(..1).slice(1..).peek_byte().unwrap(), None);
+    }
+
+    #[test]
+    fn slice_many_bounds() {
+        let tmp = fixture(100);
+        let mut parent = tmp.slice().slice(10..90);
+        parent.seek(SeekFrom::Start(5)).unwrap();
+        let slices = parent.slice_many([0..10, 20..30, 70..200]);
+        let bounds = slices
+            .iter()
+            .map(|s| (s.start_pos(), s.end_pos(), s.cursor_pos()))
+            .collect::<Vec<_>>();
+        // The last range is clamped to the parent, and each has its own cursor
+        assert_eq!(bounds, [(10, 20, 10), (30, 40, 30), (80, 90, 80)]);
+        let mut second = slices[1].clone();
+        let mut buf = Vec::new();
+        second.read_to_end(&mut buf).unwrap();
+        assert_eq!(buf, &fixture_bytes(100)[30..40]);
+        assert_eq!(slices[0].cursor_pos(), 10);
+        assert!(parent.slice_many(std::iter::empty()).is_empty());
+    }
+
+    #[test]
+    fn read_full_fills() {
+        let tmp = fixture(100);
+        let mut slice = tmp.slice().slice(10..60);
+        let mut buf = [0; 30];
+        assert_eq!(slice.read_full(&mut buf).unwrap(), 30);
+        assert_eq!(buf, fixture_bytes(100)[10..40]);
+        // Only 20 bytes are left
+        let mut buf = [0xff; 30];
+        assert_eq!(slice.read_full(&mut buf).unwrap(), 20);
+        assert_eq!(buf[..20], fixture_bytes(100)[40..60]);
+        assert_eq!(buf[20..], [0xff; 10]);
+        assert_eq!(slice.read_full(&mut buf).unwrap(), 0);
+        assert_eq!(slice.read_full(&mut []).unwrap(), 0);
+    }
+
+    #[test]
+    fn read_full_large() {
+        // Big enough that a single read might come back short
+        let tmp = fixture(3 << 20);
+        let mut buf = vec![0; 3 << 20];
+        assert_eq!(tmp.slice().read_full(&mut buf).unwrap(), 3 << 20);
+        assert_eq!(buf, fixture_bytes(3 << 20));
+    }
+
+    #[test]
+    fn read_boxed_cases() {
+        let tmp = fixture(100);
+        let mut slice = tmp.slice().slice(10..50);
+        slice.seek(SeekFrom::Start(5)).unwrap();
+        let buf = slice.read_boxed(20).unwrap();
+        assert_eq!(*buf, fixture_bytes(100)[15..35]);
+        assert_eq!(slice.cursor_pos(), 35);
+        // 15 bytes are left
+        let n = preads_during(|| {
+            let err = slice.read_boxed(16).unwrap_err();
+            assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+        });
+        assert_eq!(n, 0);
+        assert_eq!(slice.cursor_pos(), 35);
+        assert_eq!(*slice.read_boxed(15).unwrap(), fixture_bytes(100)[35..50]);
+        assert!(slice.read_boxed(0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn display_summary() {
+        let tmp = fixture(0x3000);
+        let mut slice = tmp.slice().slice(0x1000..0x2000);
+        slice.seek(SeekFrom::Start(0x40)).unwrap();
+        let s = slice.to_string();
+        assert!(s.contains("4096 bytes"), "{s}");
+        assert_eq!(s, "[0x1000..0x2000] (4096 bytes, cursor @ 0x1040)");
+        assert!(tmp.slice().slice(5..5).to_string().contains("(0 bytes"));
+    }
+
+    #[test]
+    fn check_magic_cases() {
+        let tmp = TempFile::new(b"xxPAR1 and the rest");
+        let mut slice = tmp.slice().slice(2..);
+        slice.seek(SeekFrom::Start(7)).unwrap();
+        assert!(slice.check_magic(b"PAR1").unwrap());
+        assert!(slice.check_magic(b"").unwrap());
+        assert!(!slice.check_magic(b"PAR2").unwrap());
+        assert!(!slice.check_magic(b"xxPA").unwrap());
+        assert_eq!(slice.cursor_pos(), 9);
+        // Too short, whether according to the bounds or the file
+        let short = tmp.slice().slice(2..5);
+        assert!(!short.check_magic(b"PAR1").unwrap());
+        let overstated = FileSlice::with_file_len(tmp.open(), 100).slice(15..);
+        assert!(!overstated.check_magic(b"rest!").unwrap());
+    }
+
+    #[test]
+    fn split_ndjson_records() {
+        let text = b"{\"a\":1}\n{\"b\":22}\n\n{\"c\":333}";
+        let tmp = TempFile::new(text);
+        let slice = tmp.slice();
+        let records = slice
+            .split_at_delimiters(b'\n')
+            .map(|r| r.unwrap())
+            .collect::<Vec<_>>();
+        let bounds = records
+            .iter()
+            .map(|r| (r.start_pos(), r.end_pos()))
+            .collect::<Vec<_>>();
+        // An empty record between the consecutive newlines, and the last one
+        // has no delimiter
+        assert_eq!(bounds, [(0, 7), (8, 16), (17, 17), (18, 27)]);
+        let contents = records
+            .into_iter()
+            .map(|mut r| {
+                let mut buf = vec![];
+                r.read_to_end(&mut buf).unwrap();
+                buf
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            contents,
+            [&b"{\"a\":1}"[..], b"{\"b\":22}", b"", b"{\"c\":333}"]
+        );
+        // No empty record after a trailing delimiter
+        let trailing = slice.slice(..17);
+        assert_eq!(trailing.split_at_delimiters(b'\n').count(), 2);
+        assert_eq!(slice.slice(5..5).split_at_delimiters(b'\n').count(), 0);
+    }
+
+    #[test]
+    fn split_records_across_blocks() {
+        let text = (0..3000).map(|i| format!("{i}\n")).collect::<String>();
+        let tmp = TempFile::new(text.as_bytes());
+        let records = tmp
+            .slice()
+            .split_at_delimiters(b'\n')
+            .map(|r| {
+                let mut buf = String::new();
+                r.unwrap().read_to_string(&mut buf).unwrap();
+                buf
+            })
+            .collect::<Vec<_>>();
+        let expected = (0..3000).map(|i| i.to_string()).collect::<Vec<_>>();
+        assert_eq!(records, expected);
+    }
+
+    #[test]
+    fn preallocate_1mib() {
+        let tmp = TempFile::new(b"");
+        let slice = FileSlice::preallocate(tmp.open(), 1 << 20).unwrap();
+        assert_eq!(slice.len(), 1 << 20);
+        assert_eq!(tmp.len(), 1 << 20);
+        // The new space reads back as zeros
+        assert_eq!(slice.read_range(1000..1010).unwrap(), [0; 10]);
+        // A longer file is truncated
+        let tmp = fixture(10_000);
+        let slice = FileSlice::preallocate(tmp.open(), 4096).unwrap();
+        assert_eq!(slice.len(), 4096);
+        assert_eq!(tmp.len(), 4096);
+    }
+
+    #[test]
+    fn copy_into_file() {
+        let tmp = fixture(300_000);
+        let slice = tmp.slice().slice(1234..250_000);
+        let dst = TempFile::new(b"header");
+        let mut file = dst.open();
+        file.seek(SeekFrom::End(0)).unwrap();
+        assert_eq!(slice.copy_into(&mut file).unwrap(), slice.len() as u64);
+        // It's written at the destination's cursor, which is advanced
+        assert_eq!(file.stream_position().unwrap(), 6 + slice.len() as u64);
+        let mut expected = b"header".to_vec();
+        expected.extend_from_slice(&fixture_bytes(300_000)[1234..250_000]);
+        assert_eq!(std::fs::read(dst.path()).unwrap(), expected);
+    }
+
+    #[cfg(feature = "tar")]
+    #[test]
+    fn copy_tarball_member_into_file() {
+        let contents = fixture_bytes(20_000);
+        let tmp = TempFile::new(&tarball(&[("a.txt", b"first"), ("b.bin", &contents)]));
+        let (_, member) = tar_entry(tar::Archive::new(tmp.open()), Path::new("b.bin"))
+            .unwrap()
+            .unwrap();
+        let dst = TempFile::new(b"");
+        assert_eq!(member.copy_into(&mut dst.open()).unwrap(), 20_000);
+        assert_eq!(dst.len(), 20_000);
+        assert_eq!(std::fs::read(dst.path()).unwrap(), contents);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn file_identity_across_opens() {
+        let tmp = fixture(100);
+        let a = tmp.slice().slice(10..20);
+        let b = FileSlice::new(File::open(tmp.path()).unwrap());
+        assert!(!a.is_same_file(&b));
+        assert_eq!(a.file_identity().unwrap(), b.file_identity().unwrap());
+        assert_eq!(
+            a.file_identity().unwrap(),
+            a.clone().file_identity().unwrap()
+        );
+        let other = fixture(100);
+        assert_ne!(
+            a.file_identity().unwrap(),
+            other.slice().file_identity().unwrap()
+        );
+    }
+
+    #[test]
+    fn ensure_readable_after_truncation() {
+        let tmp = fixture(100);
+        let slice = tmp.slice().slice(10..90);
+        slice.ensure_readable(0..80).unwrap();
+        slice.ensure_readable(40..40).unwrap();
+        let err = slice.ensure_readable(70..81).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+        let (start, end) = (20, 10);
+        let err = slice.ensure_readable(start..end).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        // The file shrinks so that it ends inside the slice
+        tmp.set_len(50);
+        slice.ensure_readable(0..40).unwrap();
+        let err = slice.ensure_readable(30..60).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn map_read_covers_slice() {
+        let tmp = fixture(100_000);
+        let mut slice = tmp.slice().slice(123..90_000);
+        slice.seek(SeekFrom::Start(500)).unwrap();
+        let lens = slice
+            .map_read(7000, |block| block.len())
+            .collect::<std::io::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(lens.iter().sum::<usize>(), slice.len());
+        assert!(lens[..lens.len() - 1].iter().all(|&n| n == 7000));
+        assert_eq!(*lens.last().unwrap(), slice.len() % 7000);
+        let contents = slice
+            .map_read(4096, |block| block.to_vec())
+            .collect::<std::io::Result<Vec<_>>>()
+            .unwrap()
+            .concat();
+        assert_eq!(contents, fixture_bytes(100_000)[123..90_000]);
+        assert_eq!(slice.slice(5..5).map_read(10, |b| b.len()).count(), 0);
+    }
+
+    #[test]
+    fn trim_trailing_nul_padding() {
+        let mut data = b"xxrecord\0data".to_vec();
+        data.resize(20_000, 0);
+        data.extend_from_slice(b"after");
+        let tmp = TempFile::new(&data);
+        let mut slice = tmp.slice().slice(2..20_000);
+        slice.trim_trailing(0).unwrap();
+        // The NUL in the middle is kept
+        assert_eq!((slice.start_pos(), slice.end_pos()), (2, 13));
+        slice.trim_trailing(0).unwrap();
+        assert_eq!(slice.end_pos(), 13);
+        let mut all_padding = tmp.slice().slice(13..20_000);
+        all_padding.trim_trailing(0).unwrap();
+        assert!(all_padding.is_empty());
+        assert_eq!(all_padding.start_pos(), 13);
+    }
+
+    #[test]
+    fn read_all_into_reuses_buffer() {
+        let tmp = fixture(10_000);
+        let data = fixture_bytes(10_000);
+        let mut buf = Vec::new();
+        let mut big = tmp.slice().slice(100..9_000);
+        big.seek(SeekFrom::Start(50)).unwrap();
+        assert_eq!(big.read_all_into(&mut buf).unwrap(), 8_900);
+        assert_eq!(buf, data[100..9_000]);
+        assert_eq!(big.cursor_pos(), 150);
+        let capacity = buf.capacity();
+        let small = tmp.slice().slice(20..30);
+        assert_eq!(small.read_all_into(&mut buf).unwrap(), 10);
+        assert_eq!(buf, data[20..30]);
+        assert_eq!(buf.capacity(), capacity);
+    }
+
+    #[test]
+    fn is_regular_file_cases() {
+        let tmp = fixture(10);
+        assert!(tmp.slice().is_regular_file().unwrap());
+        assert!(tmp.slice().slice(5..5).is_regular_file().unwrap());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn pipe_is_not_regular_file() {
+        let (reader, _writer) = std::io::pipe().unwrap();
+        let file = File::from(std::os::fd::OwnedFd::from(reader));
+        assert!(!FileSlice::new(file).is_regular_file().unwrap());
+    }
+
+    #[test]
+    fn split_at_offsets_cuts() {
+        let tmp = TempFile::new(b"xx0123456789yy");
+        let slice = tmp.slice().slice(2..12);
+        let lens = |pieces: Vec<FileSlice>| pieces.iter().map(|p| p.len()).collect::<Vec<_>>();
+        let pieces = slice.split_at_offsets(&[3, 7]);
+        let contents = pieces
+            .iter()
+            .map(|p| {
+                let mut buf = vec![];
+                p.clone().read_to_end(&mut buf).unwrap();
+                buf
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(contents, [&b"012"[..], b"3456", b"789"]);
+        assert_eq!(lens(pieces), [3, 4, 3]);
+        // Unsorted, duplicated, and out-of-range cuts
+        assert_eq!(lens(slice.split_at_offsets(&[7, 3, 7, 100])), [3, 4, 3, 0]);
+        assert_eq!(lens(slice.split_at_offsets(&[0])), [0, 10]);
+        assert_eq!(lens(slice.split_at_offsets(&[])), [10]);
+    }
+
+    #[test]
+    fn metadata_is_whole_file() {
+        let tmp = fixture(100);
+        let slice = tmp.slice().slice(10..20);
+        let meta = slice.metadata().unwrap();
+        assert_eq!(meta.len(), 100);
+        assert_eq!(meta.len(), tmp.len());
+        assert!(meta.is_file());
+        tmp.append(&[0; 5]);
+        assert_eq!(slice.metadata().unwrap().len(), 105);
+    }
+
+    #[test]
+    fn shrink_start_and_end() {
+        let tmp = fixture(100);
+        let mut slice = tmp.slice().slice(10..90);
+        slice.seek(SeekFrom::Start(2)).unwrap();
+        slice.shrink_start(5);
+        assert_eq!(slice.len(), 75);
+        assert_eq!((slice.start_pos(), slice.cursor_pos()), (15, 15));
+        slice.seek(SeekFrom::Start(70)).unwrap();
+        slice.shrink_end(10);
+        assert_eq!(slice.len(), 65);
+        assert_eq!((slice.end_pos(), slice.cursor_pos()), (80, 80));
+        // A cursor inside the remaining part doesn't move
+        slice.seek(SeekFrom::Start(20)).unwrap();
+        slice.shrink_start(3);
+        slice.shrink_end(3);
+        assert_eq!(
+            (slice.start_pos(), slice.end_pos(), slice.cursor_pos()),
+            (18, 77, 35)
+        );
+        // Can't invert
+        let mut a = slice.clone();
+        a.shrink_start(1000);
+        assert_eq!((a.start_pos(), a.end_pos(), a.cursor_pos()), (77, 77, 77));
+        let mut b = slice.clone();
+        b.shrink_end(1000);
+        assert_eq!((b.start_pos(), b.end_pos(), b.cursor_pos()), (18, 18, 18));
+        let mut c = slice.clone();
+        c.shrink_start(u64::MAX);
+        assert!(c.is_empty());
+    }
+
+    #[test]
+    fn boxed_read_seek() {
+        let tmp = fixture(100);
+        let data = fixture_bytes(100);
+        let mut readers: Vec<Box<dyn ReadSeek>> = vec![
+            tmp.slice().slice(..10).boxed(),
+            tmp.slice().slice(40..55).boxed(),
+            Box::new(std::io::Cursor::new(b"not a file".to_vec())),
+        ];
+        let mut contents = vec![];
+        for r in &mut readers {
+            let mut buf = vec![];
+            r.read_to_end(&mut buf).unwrap();
+            contents.push(buf);
+        }
+        assert_eq!(contents, [&data[..10], &data[40..55], b"not a file"]);
+        readers[1].seek(SeekFrom::Start(5)).unwrap();
+        let mut buf = [0; 3];
+        readers[1].read_exact(&mut buf).unwrap();
+        assert_eq!(buf, data[45..48]);
+    }
+
+    #[test]
+    fn readable_bytes_after_truncation() {
+        let tmp = fixture(100);
+        let mut slice = tmp.slice().slice(10..90);
+        slice.seek(SeekFrom::Start(20)).unwrap();
+        assert_eq!(slice.readable_bytes().unwrap(), 60);
+        tmp.set_len(50);
+        assert_eq!(slice.readable_bytes().unwrap(), 20);
+        assert_eq!(slice.bytes_remaining(), 60);
+        // Truncated to before the cursor
+        tmp.set_len(25);
+        assert_eq!(slice.readable_bytes().unwrap(), 0);
+        assert_eq!(slice.bytes_remaining(), 60);
+        slice.seek(SeekFrom::End(10)).unwrap();
+        assert_eq!(slice.readable_bytes().unwrap(), 0);
+    }
+
+    #[test]
+    fn offset_of_cases() {
+        let tmp = fixture(100);
+        let whole = tmp.slice();
+        let a = whole.slice(10..20);
+        let b = whole.slice(64..);
+        assert_eq!(a.offset_of(&b), Some(54));
+        assert_eq!(b.offset_of(&a), Some(-54));
+        assert_eq!(a.offset_of(&a.clone()), Some(0));
+        // A separately-opened file is a different file, even with the same path
+        let reopened = FileSlice::new(tmp.open());
+        assert_eq!(a.offset_of(&reopened), None);
+        let other = fixture(100);
+        assert_eq!(a.offset_of(&other.slice()), None);
+    }
+
+    /// Every full-read path, over a sub-slice big enough that the OS may
+    /// split it.  This uses only portable APIs, so it also covers the WASI
+    /// read path when run under a WASI runtime.
+    #[test]
+    fn full_reads_through_pread() {
+        let tmp = fixture(1 << 20);
+        let data = fixture_bytes(1 << 20);
+        let slice = tmp.slice().slice(1000..(1 << 20) - 1000);
+        let expected = &data[1000..(1 << 20) - 1000];
+
+        let mut buf = vec![0; slice.len()];
+        slice.clone().read_exact(&mut buf).unwrap();
+        assert_eq!(buf, expected);
+        let mut buf = vec![0; slice.len() + 10];
+        assert_eq!(slice.clone().read_full(&mut buf).unwrap(), slice.len());
+        assert_eq!(&buf[..slice.len()], expected);
+        let mut buf = vec![];
+        slice.clone().read_to_end(&mut buf).unwrap();
+        assert_eq!(buf, expected);
+        let mut buf = vec![0; 5000];
+        slice.pread_exact(&mut buf, 301_000).unwrap();
+        assert_eq!(buf, data[301_000..306_000]);
+    }
+
+    #[cfg(feature = "zerocopy")]
+    #[test]
+    fn read_struct_header() {
+        #[derive(zerocopy::FromBytes, Debug, PartialEq)]
+        #[repr(C)]
+        struct Header {
+            magic: [u8; 4],
+            version: u16,
+            flags: u16,
+            len: u32,
+        }
+        let mut data = b"x".to_vec();
+        data.extend_from_slice(b"HDR1");
+        data.extend_from_slice(&3u16.to_ne_bytes());
+        data.extend_from_slice(&0x8001u16.to_ne_bytes());
+        data.extend_from_slice(&1234u32.to_ne_bytes());
+        data.extend_from_slice(b"body");
+        let tmp = TempFile::new(&data);
+        // Starting at an odd offset, to check alignment doesn't matter
+        let mut slice = tmp.slice().slice(1..);
+        let header = slice.read_struct::<Header>().unwrap();
+        let expected = Header {
+            magic: *b"HDR1",
+            version: 3,
+            flags: 0x8001,
+            len: 1234,
+        };
+        assert_eq!(header, expected);
+        assert_eq!(slice.cursor_pos(), 13);
+        // Only the 4-byte body is left
+        let err = slice.read_struct::<Header>().unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+        assert_eq!(slice.cursor_pos(), 13);
+        assert_eq!(slice.read_struct::<[u8; 4]>().unwrap(), *b"body");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn advise_range_sub_ranges() {
+        let tmp = fixture(100_000);
+        let slice = tmp.slice().slice(10_000..90_000);
+        let all = [
+            Advice::Normal,
+            Advice::Sequential,
+            Advice::Random,
+            Advice::WillNeed,
+            Advice::DontNeed,
+            Advice::NoReuse,
+        ];
+        for advice in all {
+            slice.advise_range(70_000..80_000, advice).unwrap();
+        }
+        // Empty, inverted, and out-of-range ranges are fine too
+        let (start, end) = (500, 100);
+        slice.advise_range(start..end, Advice::DontNeed).unwrap();
+        slice.advise_range(100..100, Advice::WillNeed).unwrap();
+        slice
+            .advise_range(79_000..200_000, Advice::DontNeed)
+            .unwrap();
+        slice
+            .advise_range(200_000..300_000, Advice::DontNeed)
+            .unwrap();
+        assert_eq!(
+            slice.read_range(60_000..80_000).unwrap(),
+            &fixture_bytes(100_000)[70_000..90_000]
+        );
+    }
+
+    #[test]
+    fn rebase_onto_reopened_file() {
+        let tmp = fixture(100);
+        let mut slice = tmp.slice().slice(10..50);
+        let old = slice.clone();
+        let mut buf = [0; 10];
+        slice.read_exact(&mut buf).unwrap();
+        slice.rebase(tmp.open());
+        assert!(!slice.is_same_file(&old));
+        assert_eq!(
+            (slice.start_pos(), slice.end_pos(), slice.cursor_pos()),
+            (10, 50, 20)
+        );
+        // Reads carry on from where they left off
+        let mut buf = vec![];
+        slice.read_to_end(&mut buf).unwrap();
+        assert_eq!(buf, &fixture_bytes(100)[20..50]);
+        // The original `File` is now only held by the clone
+        assert_eq!(Arc::strong_count(&old.file), 1);
+    }
+
+    #[test]
+    fn read_at_timeout_completes() {
+        let tmp = fixture(100);
+        let mut slice = tmp.slice().slice(10..90);
+        slice.seek(SeekFrom::Start(50)).unwrap();
+        let timeout = std::time::Duration::from_secs(10);
+        let mut buf = [0; 20];
+        assert_eq!(slice.read_at_timeout(&mut buf, 5, timeout).unwrap(), 20);
+        assert_eq!(buf, fixture_bytes(100)[15..35]);
+        assert_eq!(slice.cursor_pos(), 60);
+        // Stops at the end of the slice
+        assert_eq!(slice.read_at_timeout(&mut buf, 70, timeout).unwrap(), 10);
+        assert_eq!(buf[..10], fixture_bytes(100)[80..90]);
+        assert_eq!(slice.read_at_timeout(&mut buf, 80, timeout).unwrap(), 0);
+        assert_eq!(slice.read_at_timeout(&mut buf, 200, timeout).unwrap(), 0);
+    }
+
+    #[test]
+    fn shrink_to_readable_after_truncation() {
+        let tmp = fixture(100);
+        let mut slice = tmp.slice().slice(10..90);
+        slice.shrink_to_readable().unwrap();
+        assert_eq!(slice.len(), 80);
+        tmp.set_len(50);
+        slice.shrink_to_readable().unwrap();
+        assert_eq!(slice.len(), 40);
+        assert_eq!(slice.end_pos(), 50);
+        // Growing the file doesn't move the end forwards again
+        tmp.append(&[0; 100]);
+        slice.shrink_to_readable().unwrap();
+        assert_eq!(slice.len(), 40);
+        // The end never goes before the start
+        tmp.set_len(5);
+        slice.shrink_to_readable().unwrap();
+        assert!(slice.is_empty());
+        assert_eq!(slice.start_pos(), 10);
+    }
+
+    #[test]
+    fn read_sequence_records() {
+        let tmp = TempFile::new(b"hdr:aaaabbbbccccdd");
+        let mut slice = tmp.slice().slice(4..);
+        let record = |s: &mut FileSlice| {
+            let mut buf = [0; 4];
+            s.read_exact(&mut buf)?;
+            Ok(buf)
+        };
+        let records = slice.read_sequence(3, record).unwrap();
+        assert_eq!(records, [*b"aaaa", *b"bbbb", *b"cccc"]);
+        assert_eq!(slice.cursor_pos(), 16);
+        // The fourth record is short, so the error comes straight back
+        slice.seek(SeekFrom::Start(0)).unwrap();
+        let mut calls = 0;
+        let err = slice
+            .read_sequence(10, |s| {
+                calls += 1;
+                record(s)
+            })
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+        assert_eq!(calls, 4);
+        assert!(slice.read_sequence(0, record).unwrap().is_empty());
+    }
+
+    #[test]
+    fn clone_reset_vs_clone() {
+        let tmp = fixture(100);
+        let mut slice = tmp.slice().slice(10..50);
+        slice.seek(SeekFrom::Start(15)).unwrap();
+        let cloned = slice.clone();
+        let mut reset = slice.clone_reset();
+        assert_eq!(cloned.cursor_pos(), 25);
+        assert_eq!(reset.cursor_pos(), 10);
+        assert_eq!(reset.key(), slice.key());
+        let mut buf = vec![];
+        reset.read_to_end(&mut buf).unwrap();
+        assert_eq!(buf, &fixture_bytes(100)[10..50]);
+        // The original's cursor isn't affected
+        assert_eq!(slice.cursor_pos(), 25);
+    }
+
+    #[cfg(feature = "tar")]
+    #[test]
+    fn sort_by_offset_reorders() {
+        let header = |path: &str| {
+            let mut h = tar::Header::new_gnu();
+            h.set_path(path).unwrap();
+            h
+        };
+        let mut headers = vec![
+            (header("d"), 3072, 3078),
+            (header("c"), 2048, 2048),
+            (header("c2"), 2048, 2048),
+            (header("b"), 1024, 2024),
+            (header("a"), 512, 518),
+        ];
+        sort_by_offset(&mut headers);
+        let order = headers
+            .iter()
+            .map(|(h, start, end)| (h.path().unwrap().into_owned(), *start, *end))
+            .collect::<Vec<_>>();
+        let expected = [
+            ("a", 512, 518),
+            ("b", 1024, 2024),
+            ("c", 2048, 2048),
+            ("c2", 2048, 2048),
+            ("d", 3072, 3078),
+        ]
+        .map(|(p, start, end)| (PathBuf::from(p), start, end));
+        assert_eq!(order, expected);
+    }
+
+    /// On Windows, `seek_read` moves the handle's own cursor as a side-effect,
+    /// and a second handle appending to the file races with the reads; the
+    /// slice should still return exactly its declared bytes.
+    #[cfg(windows)]
+    #[test]
+    fn windows_read_to_end_while_appending() {
+        let len = 4 * 1024 * 1024;
+        let tmp = fixture(len);
+        let slice = tmp.slice().slice(1000..len as u64 - 1000);
+        let mut a = slice.clone();
+        let mut b = slice.clone();
+        let path = tmp.path().to_owned();
+        let writer = std::thread::spawn(move || {
+            let mut file = OpenOptions::new().append(true).open(path).unwrap();
+            for _ in 0..64 {
+                file.write_all(&[0xff; 4096]).unwrap();
+            }
+        });
+        // Interleave reads from two clones which share one handle
+        let mut buf_a = vec![0; 12345];
+        a.read_exact(&mut buf_a).unwrap();
+        let mut buf_b = vec![];
+        b.read_to_end(&mut buf_b).unwrap();
+        a.read_to_end(&mut buf_a).unwrap();
+        writer.join().unwrap();
+        let expected = &fixture_bytes(len)[1000..len - 1000];
+        assert_eq!(buf_a, expected);
+        assert_eq!(buf_b, expected);
+        assert_eq!(tmp.len(), len as u64 + 64 * 4096);
+    }
+
+    /// The file shrinks after the slice was made: `read_to_end` returns what's
+    /// there, and only allocates for the declared length of the slice
+    #[cfg(windows)]
+    #[test]
+    fn windows_read_to_end_after_shrinking() {
+        let tmp = fixture(100_000);
+        let mut slice = tmp.slice().slice(10_000..);
+        tmp.set_len(50_000);
+        let mut buf = vec![];
+        assert_eq!(slice.read_to_end(&mut buf).unwrap(), 40_000);
+        assert_eq!(buf, &fixture_bytes(100_000)[10_000..50_000]);
+        assert!(buf.capacity() <= 90_000);
+        // Growing it back doesn't extend the slice beyond its declared end
+        tmp.append(&[0xff; 100_000]);
+        let mut buf = vec![];
+        assert_eq!(slice.clone_reset().read_to_end(&mut buf).unwrap(), 90_000);
+        assert_eq!(buf[..40_000], fixture_bytes(100_000)[10_000..50_000]);
+        assert_eq!(buf[40_000..], [0xff; 50_000]);
+    }
 }
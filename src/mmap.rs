@@ -0,0 +1,232 @@
+use super::*;
+use std::os::unix::io::AsRawFd;
+
+impl FileSlice {
+    /// Memory-map a window of the slice
+    ///
+    /// The window begins `off` bytes into the slice and is `len` bytes long,
+    /// clamped to the end of the slice.  Page alignment is handled internally:
+    /// the returned [`MappedSlice`] derefs to exactly the bytes requested.
+    ///
+    /// # Safety
+    ///
+    /// The usual caveats of memory-mapping apply: if the underlying file is
+    /// modified or truncated while the mapping is alive, the contents of the
+    /// `&[u8]` may change underneath you, or accessing it may raise `SIGBUS`.
+    pub unsafe fn map_region(&self, off: u64, len: u64) -> std::io::Result<MappedSlice> {
+        let start = self.start.saturating_add(off).min(self.end);
+        let end = start.saturating_add(len).min(self.end);
+        let len = usize::try_from(end - start)
+            .map_err(|_| std::io::Error::other("Region is too large to map"))?;
+        if len == 0 {
+            return Ok(MappedSlice {
+                ptr: std::ptr::null_mut(),
+                map_len: 0,
+                offset: 0,
+                len: 0,
+            });
+        }
+        let page_size = match libc::sysconf(libc::_SC_PAGESIZE) {
+            -1 => return Err(std::io::Error::last_os_error()),
+            x => x as u64,
+        };
+        let aligned_start = start - start % page_size;
+        let offset = (start - aligned_start) as usize;
+        let map_len = offset + len;
+        let ptr = libc::mmap(
+            std::ptr::null_mut(),
+            map_len,
+            libc::PROT_READ,
+            libc::MAP_SHARED,
+            self.file.as_raw_fd(),
+            libc::off_t::try_from(aligned_start)
+                .map_err(|_| std::io::Error::other("Offset too large"))?,
+        );
+        if ptr == libc::MAP_FAILED {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(MappedSlice {
+            ptr,
+            map_len,
+            offset,
+            len,
+        })
+    }
+}
+
+impl FileSlice {
+    /// Memory-map the whole underlying file, sharing the mapping with all
+    /// other slices of it
+    ///
+    /// The first call maps the whole file (at its current length).  After
+    /// that, calling this on this slice or any slice which shares its `File`
+    /// (ie. any clone or sub-slice) returns the same mapping, so you only pay
+    /// for one mapping however many slices there are.  See
+    /// [`FileSlice::mapped_window`] to get just the part of the mapping which
+    /// is covered by this slice.
+    ///
+    /// # Safety
+    ///
+    /// The same caveats apply as for [`FileSlice::map_region`].
+    pub unsafe fn shared_mmap(&self) -> std::io::Result<Arc<MappedSlice>> {
+        if let Some(map) = self.mmap.get() {
+            return Ok(map.clone());
+        }
+        let len = self.file.metadata()?.len();
+        let map = Arc::new(self.derive(0, len, 0).map_region(0, len)?);
+        // If another thread got there first, use their mapping instead
+        Ok(self.mmap.get_or_init(|| map).clone())
+    }
+
+    /// The part of the shared mapping (see [`FileSlice::shared_mmap`]) which
+    /// is covered by this slice
+    ///
+    /// If the file has grown since the mapping was made, the window is
+    /// clamped to the end of the mapping.
+    ///
+    /// # Safety
+    ///
+    /// The same caveats apply as for [`FileSlice::map_region`].
+    pub unsafe fn mapped_window(&self) -> std::io::Result<MappedWindow> {
+        let map = self.shared_mmap()?;
+        let end = self.end.min(map.len() as u64) as usize;
+        let start = (self.start as usize).min(end);
+        Ok(MappedWindow { map, start, end })
+    }
+}
+
+/// A window onto a shared mapping of a file
+///
+/// See [`FileSlice::mapped_window`].
+#[derive(Clone, Debug)]
+pub struct MappedWindow {
+    map: Arc<MappedSlice>,
+    start: usize,
+    end: usize,
+}
+
+impl MappedWindow {
+    /// The mapping of the whole file which this is a window onto
+    pub fn mapping(&self) -> &Arc<MappedSlice> {
+        &self.map
+    }
+}
+
+impl std::ops::Deref for MappedWindow {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.map[self.start..self.end]
+    }
+}
+
+impl AsRef<[u8]> for MappedWindow {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+/// A read-only memory-mapped window of a file
+///
+/// See [`FileSlice::map_region`].  The mapping is removed when this is dropped.
+#[derive(Debug)]
+pub struct MappedSlice {
+    /// The start of the mapping, which is page-aligned
+    ptr: *mut libc::c_void,
+    map_len: usize,
+    /// The offset of the requested bytes within the mapping
+    offset: usize,
+    len: usize,
+}
+
+// SAFETY: The mapping is read-only, and isn't tied to any thread
+unsafe impl Send for MappedSlice {}
+unsafe impl Sync for MappedSlice {}
+
+impl std::ops::Deref for MappedSlice {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        if self.len == 0 {
+            return &[];
+        }
+        // SAFETY: `ptr` points to a live mapping of `map_len` bytes, and
+        // `offset + len == map_len`
+        unsafe { std::slice::from_raw_parts((self.ptr as *const u8).add(self.offset), self.len) }
+    }
+}
+
+impl AsRef<[u8]> for MappedSlice {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl Drop for MappedSlice {
+    fn drop(&mut self) {
+        if self.map_len != 0 {
+            // SAFETY: We own the mapping, and nothing can borrow from it after
+            // this point
+            unsafe { libc::munmap(self.ptr, self.map_len) };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::*;
+
+    #[test]
+    fn map_region_near_end() {
+        let tmp = fixture(3 * 4096 + 100);
+        let slice = tmp.slice().slice(1000..3 * 4096 + 50);
+        let len = slice.len() as u64;
+        // An unaligned window which ends exactly at the end of the slice
+        let map = unsafe { slice.map_region(len - 300, 300) }.unwrap();
+        assert_eq!(&map[..], &slice.read_range(len - 300..).unwrap()[..]);
+        let map = unsafe { slice.map_region(5, 10) }.unwrap();
+        assert_eq!(&map[..], &slice.read_range(5..15).unwrap()[..]);
+    }
+
+    #[test]
+    fn map_region_zero_len() {
+        let tmp = fixture(100);
+        let slice = tmp.slice().slice(10..90);
+        assert!(unsafe { slice.map_region(5, 0) }.unwrap().is_empty());
+        assert!(unsafe { slice.map_region(80, 10) }.unwrap().is_empty());
+        assert!(unsafe { slice.map_region(u64::MAX, u64::MAX) }
+            .unwrap()
+            .is_empty());
+        assert!(unsafe { tmp.slice().slice(50..50).map_region(0, 10) }
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn map_region_clamped_to_end() {
+        let tmp = fixture(100);
+        let slice = tmp.slice().slice(10..90);
+        let map = unsafe { slice.map_region(70, 1000) }.unwrap();
+        assert_eq!(&map[..], &fixture_bytes(100)[80..90]);
+        let map = unsafe { slice.map_region(0, u64::MAX) }.unwrap();
+        assert_eq!(&map[..], &fixture_bytes(100)[10..90]);
+    }
+
+    #[test]
+    fn shared_mmap_is_shared() {
+        let tmp = fixture(100);
+        let whole = tmp.slice();
+        let sub = whole.slice(10..20);
+        let a = unsafe { sub.shared_mmap() }.unwrap();
+        let b = unsafe { whole.clone().shared_mmap() }.unwrap();
+        assert!(Arc::ptr_eq(&a, &b));
+        assert_eq!(&a[..], &fixture_bytes(100)[..]);
+        let window = unsafe { sub.mapped_window() }.unwrap();
+        assert!(Arc::ptr_eq(window.mapping(), &a));
+        assert_eq!(&window[..], &fixture_bytes(100)[10..20]);
+        // A slice made from a fresh `File` gets its own mapping
+        let c = unsafe { tmp.slice().shared_mmap() }.unwrap();
+        assert!(!Arc::ptr_eq(&a, &c));
+    }
+}
@@ -0,0 +1,112 @@
+use super::*;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// A set of open files, from which slices can be taken by path
+///
+/// The pool keeps one `File` open for each distinct file on disk (as
+/// identified by [`FileId`]), and all slices of that file share it.  So
+/// asking for two slices of the same path (or of two paths which are links to
+/// the same file) only costs one fd.  This is handy for servers which serve
+/// ranges of many files and want to keep their fd usage under control.
+///
+/// A file stays open for as long as the pool holds it, even if all the slices
+/// of it have been dropped; use [`FileSlicePool::clear`] to close them.
+/// Since it relies on `FileId`, the pool currently only works on unix.
+#[derive(Debug, Default)]
+pub struct FileSlicePool {
+    files: Mutex<HashMap<FileId, FileSlice>>,
+}
+
+impl FileSlicePool {
+    pub fn new() -> FileSlicePool {
+        FileSlicePool::default()
+    }
+
+    /// Take a slice of the file at `path`
+    ///
+    /// If the pool already has the file open, the slice shares its `File`;
+    /// otherwise it's opened (read-only) and added to the pool.  The range is
+    /// interpreted as for [`FileSlice::slice`], relative to the file's
+    /// current length.
+    pub fn slice<P, R>(&self, path: P, range: R) -> std::io::Result<FileSlice>
+    where
+        P: AsRef<Path>,
+        R: RangeBounds<u64>,
+    {
+        let meta = std::fs::metadata(path.as_ref())?;
+        let id = FileId::from_metadata(&meta)?;
+        let mut files = self.files.lock().unwrap();
+        let whole = match files.get(&id) {
+            Some(slice) => slice.derive(0, meta.len(), 0),
+            None => {
+                let file = File::open(path)?;
+                // The path may have been replaced since we looked it up
+                let meta = file.metadata()?;
+                let id = FileId::from_metadata(&meta)?;
+                let slice = FileSlice::with_file_len(file, meta.len());
+                files.entry(id).or_insert(slice).derive(0, meta.len(), 0)
+            }
+        };
+        Ok(whole.slice(range))
+    }
+
+    /// The number of files which the pool has open
+    pub fn len(&self) -> usize {
+        self.files.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Let go of all the files in the pool
+    ///
+    /// Slices which have already been handed out keep working, but the pool
+    /// will open files afresh the next time they're asked for.
+    pub fn clear(&self) {
+        self.files.lock().unwrap().clear()
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::tests::*;
+
+    #[test]
+    fn same_path_shares_fd() {
+        let tmp = fixture(100);
+        let pool = FileSlicePool::new();
+        let mut a = pool.slice(tmp.path(), 10..20).unwrap();
+        let b = pool.slice(tmp.path(), 50..).unwrap();
+        assert_eq!(pool.len(), 1);
+        assert!(a.is_same_file(&b));
+        // One `File`, held by the pool and the two slices
+        assert_eq!(Arc::strong_count(&a.file), 3);
+        assert_eq!((b.start_pos(), b.end_pos()), (50, 100));
+        let mut buf = vec![];
+        a.read_to_end(&mut buf).unwrap();
+        assert_eq!(buf, &fixture_bytes(100)[10..20]);
+
+        let other = fixture(10);
+        let c = pool.slice(other.path(), ..).unwrap();
+        assert_eq!(pool.len(), 2);
+        assert!(!c.is_same_file(&a));
+
+        pool.clear();
+        assert!(pool.is_empty());
+        assert_eq!(Arc::strong_count(&a.file), 2);
+        let d = pool.slice(tmp.path(), ..).unwrap();
+        assert!(!d.is_same_file(&a));
+    }
+
+    #[test]
+    fn sees_growth() {
+        let tmp = fixture(100);
+        let pool = FileSlicePool::new();
+        assert_eq!(pool.slice(tmp.path(), ..).unwrap().len(), 100);
+        tmp.append(&[1; 50]);
+        assert_eq!(pool.slice(tmp.path(), ..).unwrap().len(), 150);
+    }
+}
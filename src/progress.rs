@@ -0,0 +1,99 @@
+use super::*;
+
+impl FileSlice {
+    /// Wrap the slice so that `cb` is told how much has been read
+    ///
+    /// See [`ProgressFileSlice`].
+    pub fn with_progress<F: FnMut(u64)>(self, cb: F) -> ProgressFileSlice<F> {
+        ProgressFileSlice {
+            inner: self,
+            cb,
+            total: 0,
+        }
+    }
+}
+
+/// A `FileSlice` which reports progress as it's read
+///
+/// After each successful `read`, the callback is called with the total
+/// number of bytes read so far.  This is handy for drawing a progress bar
+/// while scanning a large slice.  Seeking doesn't reset the total, so if you
+/// skip around, it counts bytes read rather than the position reached.
+pub struct ProgressFileSlice<F> {
+    inner: FileSlice,
+    cb: F,
+    total: u64,
+}
+
+impl<F> ProgressFileSlice<F> {
+    /// The total number of bytes read so far
+    pub fn total_read(&self) -> u64 {
+        self.total
+    }
+
+    /// Get back the underlying `FileSlice`, with the cursor where this one
+    /// left it
+    pub fn into_inner(self) -> FileSlice {
+        self.inner
+    }
+}
+
+impl<F> std::fmt::Debug for ProgressFileSlice<F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("ProgressFileSlice")
+            .field("inner", &self.inner)
+            .field("total", &self.total)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<F: FnMut(u64)> Read for ProgressFileSlice<F> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let x = self.inner.read(buf)?;
+        self.total += x as u64;
+        (self.cb)(self.total);
+        Ok(x)
+    }
+}
+
+impl<F> Seek for ProgressFileSlice<F> {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        self.inner.seek(pos)
+    }
+
+    fn stream_position(&mut self) -> std::io::Result<u64> {
+        self.inner.stream_position()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::*;
+
+    #[test]
+    fn final_total_is_len() {
+        let tmp = fixture(10_000);
+        let slice = tmp.slice().slice(100..9_100);
+        let len = slice.len() as u64;
+        let mut seen = vec![];
+        let mut progress = slice.with_progress(|n| seen.push(n));
+        let mut buf = [0; 1000];
+        while progress.read(&mut buf).unwrap() != 0 {}
+        assert_eq!(progress.total_read(), len);
+        drop(progress);
+        assert_eq!(seen.last(), Some(&len));
+        assert!(seen.windows(2).all(|w| w[0] <= w[1]));
+    }
+
+    #[test]
+    fn seeking_keeps_total() {
+        let tmp = fixture(100);
+        let mut progress = tmp.slice().with_progress(|_| ());
+        progress.read_exact(&mut [0; 10]).unwrap();
+        progress.seek(SeekFrom::Start(0)).unwrap();
+        progress.read_exact(&mut [0; 10]).unwrap();
+        assert_eq!(progress.total_read(), 20);
+        assert_eq!(progress.into_inner().cursor_pos(), 10);
+    }
+}
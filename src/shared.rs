@@ -0,0 +1,245 @@
+use super::*;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+impl FileSlice {
+    /// Convert the slice into one whose cursor can be advanced through a
+    /// shared reference
+    ///
+    /// See [`SharedFileSlice`].
+    pub fn into_shared(self) -> SharedFileSlice {
+        SharedFileSlice {
+            cursor: AtomicU64::new(self.cursor),
+            inner: self,
+        }
+    }
+
+    /// Convert the slice into one whose cursor is protected by a mutex
+    ///
+    /// See [`SyncFileSlice`].
+    pub fn into_sync(self) -> SyncFileSlice {
+        SyncFileSlice {
+            cursor: Mutex::new(self.cursor),
+            inner: self,
+        }
+    }
+}
+
+/// A `FileSlice` with an atomic cursor
+///
+/// `Read` is implemented for `&SharedFileSlice`, so you can pass `&shared`
+/// to code which expects an `impl Read`, or read from several threads at
+/// once.  Each byte is returned by exactly one read: concurrent readers each
+/// get a distinct chunk of the slice.
+///
+/// The bounds and EOF behaviour are identical to those of `FileSlice`.
+#[derive(Debug)]
+pub struct SharedFileSlice {
+    /// The cursor of `inner` is ignored
+    inner: FileSlice,
+    cursor: AtomicU64,
+}
+
+impl SharedFileSlice {
+    /// The next byte to be read, as an offset into the underlying file
+    pub fn cursor_pos(&self) -> u64 {
+        self.cursor.load(Ordering::Acquire)
+    }
+
+    /// Convert back into a regular `FileSlice`, keeping the cursor position
+    pub fn into_inner(self) -> FileSlice {
+        FileSlice {
+            cursor: self.cursor.into_inner(),
+            ..self.inner
+        }
+    }
+}
+
+impl Read for &SharedFileSlice {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        loop {
+            let cursor = self.cursor.load(Ordering::Acquire);
+            let remaining = self.inner.end.saturating_sub(cursor);
+            if remaining == 0 {
+                return Ok(0);
+            }
+            let buf = if buf.len() as u64 <= remaining {
+                &mut *buf
+            } else {
+                &mut buf[..remaining as usize]
+            };
+            let x = self.inner.pread(buf, cursor)?;
+            // If someone else moved the cursor while we were reading, then
+            // they've already claimed (some of) these bytes, so try again
+            if self
+                .cursor
+                .compare_exchange(
+                    cursor,
+                    cursor + x as u64,
+                    Ordering::AcqRel,
+                    Ordering::Acquire,
+                )
+                .is_ok()
+            {
+                return Ok(x);
+            }
+        }
+    }
+}
+
+impl Read for SharedFileSlice {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        (&*self).read(buf)
+    }
+}
+
+/// A `FileSlice` with a mutex-protected cursor
+///
+/// Like [`SharedFileSlice`], `Read` is implemented for `&SyncFileSlice`, so
+/// it can be stored in an `Arc` and read from through any of its clones.  The
+/// difference is that the lock is held for the duration of each read, so
+/// concurrent reads wait their turn rather than racing and retrying.  This
+/// wastes less work when many threads are reading at once.
+#[derive(Debug)]
+pub struct SyncFileSlice {
+    /// The cursor of `inner` is ignored
+    inner: FileSlice,
+    cursor: Mutex<u64>,
+}
+
+impl SyncFileSlice {
+    /// The next byte to be read, as an offset into the underlying file
+    pub fn cursor_pos(&self) -> u64 {
+        *self.cursor.lock().unwrap()
+    }
+
+    /// Convert back into a regular `FileSlice`, keeping the cursor position
+    pub fn into_inner(self) -> FileSlice {
+        FileSlice {
+            cursor: self.cursor.into_inner().unwrap(),
+            ..self.inner
+        }
+    }
+}
+
+impl Read for &SyncFileSlice {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let mut cursor = self.cursor.lock().unwrap();
+        let remaining = self.inner.end.saturating_sub(*cursor);
+        if remaining == 0 {
+            return Ok(0);
+        }
+        let buf = if buf.len() as u64 <= remaining {
+            buf
+        } else {
+            &mut buf[..remaining as usize]
+        };
+        let x = self.inner.pread(buf, *cursor)?;
+        *cursor += x as u64;
+        Ok(x)
+    }
+}
+
+impl Read for SyncFileSlice {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        (&*self).read(buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::*;
+
+    fn read_all(mut r: impl Read) -> Vec<u8> {
+        let mut buf = vec![];
+        r.read_to_end(&mut buf).unwrap();
+        buf
+    }
+
+    /// A file made of consecutive little-endian `u32`s, so that every 4-byte
+    /// word is distinct
+    fn words(n: u32) -> TempFile {
+        TempFile::new(&(0..n).flat_map(u32::to_le_bytes).collect::<Vec<_>>())
+    }
+
+    /// Read 4-byte words from `r` until EOF
+    fn read_words(mut r: impl Read) -> Vec<u32> {
+        let mut out = vec![];
+        loop {
+            let mut buf = [0; 4];
+            match r.read(&mut buf).unwrap() {
+                0 => return out,
+                n => {
+                    assert_eq!(n, 4);
+                    out.push(u32::from_le_bytes(buf));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn shared_as_impl_read() {
+        let tmp = fixture(100);
+        let shared = tmp.slice().slice(10..90).into_shared();
+        let mut buf = [0; 10];
+        (&shared).read_exact(&mut buf).unwrap();
+        assert_eq!(buf, fixture_bytes(100)[10..20]);
+        assert_eq!(read_all(&shared), &fixture_bytes(100)[20..90]);
+        assert_eq!(shared.cursor_pos(), 90);
+        assert!(read_all(&shared).is_empty());
+        assert_eq!(shared.into_inner().cursor_pos(), 90);
+    }
+
+    #[test]
+    fn shared_each_byte_read_once() {
+        let n = 100_000;
+        let tmp = words(n);
+        let shared = tmp.slice().into_shared();
+        let (mut a, b) = std::thread::scope(|s| {
+            let a = s.spawn(|| read_words(&shared));
+            let b = s.spawn(|| read_words(&shared));
+            (a.join().unwrap(), b.join().unwrap())
+        });
+        // Each thread sees its words in order
+        assert!(a.windows(2).all(|w| w[0] < w[1]));
+        assert!(b.windows(2).all(|w| w[0] < w[1]));
+        a.extend(b);
+        a.sort();
+        assert_eq!(a, (0..n).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn sync_through_arc_clones() {
+        let tmp = fixture(100);
+        let a = Arc::new(tmp.slice().slice(10..90).into_sync());
+        let b = a.clone();
+        let mut buf = [0; 10];
+        (&*a).read_exact(&mut buf).unwrap();
+        assert_eq!(buf, fixture_bytes(100)[10..20]);
+        (&*b).read_exact(&mut buf).unwrap();
+        assert_eq!(buf, fixture_bytes(100)[20..30]);
+        assert_eq!(a.cursor_pos(), 30);
+        assert_eq!(read_all(&*b), &fixture_bytes(100)[30..90]);
+        assert!(read_all(&*a).is_empty());
+    }
+
+    #[test]
+    fn sync_each_byte_read_once() {
+        let n = 100_000;
+        let tmp = words(n);
+        let sync = Arc::new(tmp.slice().into_sync());
+        let threads = (0..2)
+            .map(|_| {
+                let sync = sync.clone();
+                std::thread::spawn(move || read_words(&*sync))
+            })
+            .collect::<Vec<_>>();
+        let mut all = threads
+            .into_iter()
+            .flat_map(|t| t.join().unwrap())
+            .collect::<Vec<_>>();
+        all.sort();
+        assert_eq!(all, (0..n).collect::<Vec<_>>());
+    }
+}
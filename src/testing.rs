@@ -0,0 +1,80 @@
+use super::*;
+use std::io::Write;
+
+impl FileSlice {
+    /// Make a slice containing the given bytes
+    ///
+    /// This is for testing code which takes a `FileSlice`, without having to
+    /// set up a file by hand.  The bytes are written to an anonymous file
+    /// (made with `memfd_create` on Linux, or in the temp directory
+    /// elsewhere), and the returned slice covers all of it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the file can't be created or written.
+    pub fn from_vec(data: Vec<u8>) -> FileSlice {
+        let mut file = anonymous_file().unwrap();
+        file.write_all(&data).unwrap();
+        FileSlice::with_file_len(file, data.len() as u64)
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn anonymous_file() -> std::io::Result<File> {
+    use std::os::unix::io::FromRawFd;
+    // SAFETY: The name is a valid C string
+    match unsafe { libc::memfd_create(c"fileslice".as_ptr(), libc::MFD_CLOEXEC) } {
+        -1 => Err(std::io::Error::last_os_error()),
+        // SAFETY: We've just created the fd, so nothing else owns it
+        fd => Ok(unsafe { File::from_raw_fd(fd) }),
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn anonymous_file() -> std::io::Result<File> {
+    use std::sync::atomic::{AtomicU64, Ordering};
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let path = std::env::temp_dir().join(format!(
+        "fileslice-{}-{}",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed),
+    ));
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create_new(true)
+        .open(&path)?;
+    // On unix the file lives on until it's closed; elsewhere the removal may
+    // fail while it's open, in which case it's left for the OS to clean up
+    let _ = std::fs::remove_file(&path);
+    Ok(file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_literal() {
+        let mut slice = FileSlice::from_vec(b"hello, world".to_vec());
+        assert_eq!(slice.len(), 12);
+        let mut buf = String::new();
+        slice.read_to_string(&mut buf).unwrap();
+        assert_eq!(buf, "hello, world");
+        let mut sub = slice.slice(7..);
+        let mut buf = String::new();
+        sub.read_to_string(&mut buf).unwrap();
+        assert_eq!(buf, "world");
+    }
+
+    #[test]
+    fn from_empty_and_large() {
+        assert!(FileSlice::from_vec(vec![]).is_empty());
+        let data = (0..100_000).map(|i| i as u8).collect::<Vec<_>>();
+        let mut buf = vec![];
+        FileSlice::from_vec(data.clone())
+            .read_to_end(&mut buf)
+            .unwrap();
+        assert_eq!(buf, data);
+    }
+}
@@ -0,0 +1,260 @@
+use super::*;
+use std::os::unix::io::AsRawFd;
+
+impl FileSlice {
+    /// The parts of the slice which are actually backed by data on disk
+    ///
+    /// For sparse files, this lets you skip over the holes (which would read
+    /// as zeros) without reading them.  The returned ranges are relative to
+    /// the start of the slice and are in ascending order.
+    ///
+    /// This uses `lseek` with `SEEK_DATA`/`SEEK_HOLE`.  If the platform or
+    /// filesystem doesn't support this, the whole slice is returned as a
+    /// single region.  Note that it moves the fd's real cursor (which
+    /// `FileSlice` otherwise never uses).
+    pub fn data_regions(&self) -> std::io::Result<Vec<Range<u64>>> {
+        let mut whole = Vec::with_capacity(1);
+        if !self.is_empty() {
+            whole.push(0..(self.end - self.start));
+        }
+        #[cfg(any(
+            target_os = "linux",
+            target_os = "android",
+            target_os = "macos",
+            target_os = "ios",
+            target_os = "freebsd",
+        ))]
+        {
+            let fd = self.file.as_raw_fd();
+            let lseek = |pos: u64, whence| {
+                let pos = libc::off_t::try_from(pos)
+                    .map_err(|_| std::io::Error::other("Offset too large"))?;
+                // SAFETY: The fd is kept open by `self.file`
+                match unsafe { libc::lseek(fd, pos, whence) } {
+                    -1 => Err(std::io::Error::last_os_error()),
+                    x => Ok(x as u64),
+                }
+            };
+            let mut regions = vec![];
+            let mut pos = self.start;
+            while pos < self.end {
+                let data = match lseek(pos, libc::SEEK_DATA) {
+                    Ok(x) => x,
+                    // There's no more data after `pos`
+                    Err(e) if e.raw_os_error() == Some(libc::ENXIO) => break,
+                    // `SEEK_DATA` isn't supported here
+                    Err(e) if e.raw_os_error() == Some(libc::EINVAL) => return Ok(whole),
+                    Err(e) => return Err(e),
+                };
+                if data >= self.end {
+                    break;
+                }
+                let hole = lseek(data, libc::SEEK_HOLE)?.min(self.end);
+                regions.push((data - self.start)..(hole - self.start));
+                pos = hole;
+            }
+            Ok(regions)
+        }
+        #[cfg(not(any(
+            target_os = "linux",
+            target_os = "android",
+            target_os = "macos",
+            target_os = "ios",
+            target_os = "freebsd",
+        )))]
+        {
+            Ok(whole)
+        }
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "freebsd"))]
+impl FileSlice {
+    /// Call `posix_fadvise` on the given absolute range of the file
+    pub(crate) fn fadvise(&self, start: u64, end: u64, advice: libc::c_int) -> std::io::Result<()> {
+        // A length of zero would mean "until the end of the file"
+        if start >= end {
+            return Ok(());
+        }
+        let offset = start
+            .try_into()
+            .map_err(|_| std::io::Error::other("Offset too large"))?;
+        let len = (end - start)
+            .try_into()
+            .map_err(|_| std::io::Error::other("Length too large"))?;
+        // SAFETY: The fd is kept open by `self.file`
+        match unsafe { libc::posix_fadvise(self.file.as_raw_fd(), offset, len, advice) } {
+            0 => Ok(()),
+            e => Err(std::io::Error::from_raw_os_error(e)),
+        }
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl FileSlice {
+    /// Reserve space on disk for the given absolute range of the file,
+    /// extending the file if necessary
+    ///
+    /// Does nothing if the filesystem doesn't support this.
+    pub(crate) fn allocate(&self, start: u64, end: u64) -> std::io::Result<()> {
+        if start >= end {
+            return Ok(());
+        }
+        let offset = start
+            .try_into()
+            .map_err(|_| std::io::Error::other("Offset too large"))?;
+        let len = (end - start)
+            .try_into()
+            .map_err(|_| std::io::Error::other("Length too large"))?;
+        // SAFETY: The fd is kept open by `self.file`
+        match unsafe { libc::posix_fallocate(self.file.as_raw_fd(), offset, len) } {
+            0 | libc::EOPNOTSUPP => Ok(()),
+            e => Err(std::io::Error::from_raw_os_error(e)),
+        }
+    }
+
+    /// Copy from the absolute offset `from` to the end of the slice into
+    /// `dst` with `copy_file_range`, returning the offset reached
+    ///
+    /// If `copy_file_range` isn't supported for these files, this stops
+    /// early, leaving the rest for the caller to copy some other way.
+    pub(crate) fn copy_file_range(&self, mut from: u64, dst: &File) -> std::io::Result<u64> {
+        while from < self.end {
+            let mut off_in: libc::loff_t = from
+                .try_into()
+                .map_err(|_| std::io::Error::other("Offset too large"))?;
+            let len = usize::try_from(self.end - from).unwrap_or(usize::MAX);
+            // SAFETY: Both fds are kept open by their `File`s, and `off_in` is
+            // a valid pointer
+            let n = unsafe {
+                libc::copy_file_range(
+                    self.file.as_raw_fd(),
+                    &mut off_in,
+                    dst.as_raw_fd(),
+                    std::ptr::null_mut(),
+                    len,
+                    0,
+                )
+            };
+            match n {
+                // The file must have been truncated
+                0 => break,
+                -1 => match std::io::Error::last_os_error() {
+                    e if e.kind() == std::io::ErrorKind::Interrupted => (),
+                    e if matches!(
+                        e.raw_os_error(),
+                        Some(libc::EXDEV | libc::EINVAL | libc::ENOSYS | libc::EOPNOTSUPP)
+                    ) =>
+                    {
+                        break
+                    }
+                    e => return Err(e),
+                },
+                n => from += n as u64,
+            }
+        }
+        Ok(from)
+    }
+
+    /// Read into `bufs` from the given absolute offset with `preadv`, reading
+    /// at most `max` bytes
+    ///
+    /// Like std, only the first `UIO_MAXIOV` buffers are used, since the
+    /// kernel rejects any more than that.
+    pub(crate) fn preadv(
+        &self,
+        bufs: &mut [std::io::IoSliceMut],
+        pos: u64,
+        max: u64,
+    ) -> std::io::Result<usize> {
+        let mut left = max;
+        let iovecs: Vec<libc::iovec> = bufs
+            .iter_mut()
+            .take(libc::UIO_MAXIOV as usize)
+            .map(|buf| {
+                let len = (buf.len() as u64).min(left) as usize;
+                left -= len as u64;
+                libc::iovec {
+                    iov_base: buf.as_mut_ptr().cast(),
+                    iov_len: len,
+                }
+            })
+            .collect();
+        if max == 0 {
+            return Ok(0);
+        }
+        let count = iovecs.len() as libc::c_int;
+        let pos = pos
+            .try_into()
+            .map_err(|_| std::io::Error::other("Offset too large"))?;
+        // SAFETY: The fd is kept open by `self.file`, and each iovec points to
+        // (part of) a buffer which we have a `&mut` to
+        match unsafe { libc::preadv(self.file.as_raw_fd(), iovecs.as_ptr(), count, pos) } {
+            -1 => Err(std::io::Error::last_os_error()),
+            n => Ok(n as usize),
+        }
+    }
+
+    /// Deallocate the given absolute range of the file, without changing its
+    /// length
+    ///
+    /// Does nothing if the filesystem doesn't support punching holes.
+    pub(crate) fn punch_hole(&self, start: u64, end: u64) -> std::io::Result<()> {
+        if start >= end {
+            return Ok(());
+        }
+        let offset = start
+            .try_into()
+            .map_err(|_| std::io::Error::other("Offset too large"))?;
+        let len = (end - start)
+            .try_into()
+            .map_err(|_| std::io::Error::other("Length too large"))?;
+        let mode = libc::FALLOC_FL_PUNCH_HOLE | libc::FALLOC_FL_KEEP_SIZE;
+        // SAFETY: The fd is kept open by `self.file`
+        match unsafe { libc::fallocate(self.file.as_raw_fd(), mode, offset, len) } {
+            0 => Ok(()),
+            _ => match std::io::Error::last_os_error() {
+                e if e.raw_os_error() == Some(libc::EOPNOTSUPP) => Ok(()),
+                e => Err(e),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::single_range_in_vec_init)]
+mod tests {
+    use crate::tests::*;
+
+    #[test]
+    fn data_regions_of_dense_file() {
+        let tmp = fixture(10_000);
+        let slice = tmp.slice().slice(100..9000);
+        assert_eq!(slice.data_regions().unwrap(), [0..8900]);
+        assert!(slice.slice(5..5).data_regions().unwrap().is_empty());
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[test]
+    fn data_regions_excludes_punched_hole() {
+        const MB: u64 = 1024 * 1024;
+        let tmp = TempFile::new(&vec![0xff; 3 * MB as usize]);
+        let slice = tmp.slice();
+        slice.punch_hole(MB, 2 * MB).unwrap();
+        let regions = slice.data_regions().unwrap();
+        if regions == [0..3 * MB] {
+            eprintln!("Filesystem doesn't support holes; skipping");
+            return;
+        }
+        assert_eq!(regions, [0..MB, 2 * MB..3 * MB]);
+        // Relative to the start of a sub-slice, and clamped to its end
+        let sub = slice.slice(MB / 2..5 * MB / 2);
+        assert_eq!(sub.data_regions().unwrap(), [0..MB / 2, 3 * MB / 2..2 * MB]);
+        // The hole reads back as zeros
+        assert!(slice
+            .read_range(MB..2 * MB)
+            .unwrap()
+            .iter()
+            .all(|&b| b == 0));
+    }
+}
@@ -0,0 +1,214 @@
+use super::*;
+
+const SKIPPABLE_MAGIC: u32 = 0x184D2A5E;
+const SEEKABLE_MAGIC: u32 = 0x8F92EAB1;
+const FOOTER_LEN: u64 = 9;
+
+/// Information about one frame of a seekable zstd file, taken from its seek
+/// table
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FrameInfo {
+    /// The size of the frame, as stored in the file
+    pub compressed_size: u32,
+    /// The size of the frame's contents after decompression
+    pub decompressed_size: u32,
+    /// The offset of the frame's contents within the decompressed stream
+    pub decompressed_offset: u64,
+    /// The lower 32 bits of the XXH64 of the decompressed contents, if the
+    /// seek table includes checksums
+    pub checksum: Option<u32>,
+}
+
+impl FileSlice {
+    /// Split a file in the [zstd seekable format] into its frames
+    ///
+    /// This parses the seek table at the end of the slice, and returns a
+    /// slice for each of the frames it describes.  Each frame can be
+    /// decompressed independently, so you can feed any one of them to a zstd
+    /// decoder to get back that part of the original data.  The seek table
+    /// itself is not included.
+    ///
+    /// [zstd seekable format]: https://github.com/facebook/zstd/blob/dev/contrib/seekable_format/zstd_seekable_compression_format.md
+    pub fn slice_zstd_frames(self) -> std::io::Result<Vec<(FrameInfo, FileSlice)>> {
+        let invalid = |msg| std::io::Error::new(std::io::ErrorKind::InvalidData, msg);
+        let len = self.end - self.start;
+        if len < FOOTER_LEN {
+            return Err(invalid("Too short to contain a seek table"));
+        }
+        let footer = self.read_last(FOOTER_LEN)?;
+        let n_frames = u32::from_le_bytes(footer[0..4].try_into().unwrap());
+        let descriptor = footer[4];
+        if u32::from_le_bytes(footer[5..9].try_into().unwrap()) != SEEKABLE_MAGIC {
+            return Err(invalid("Seekable magic number not found"));
+        }
+        if descriptor & 0x7c != 0 {
+            return Err(invalid("Reserved bits set in seek table descriptor"));
+        }
+        let has_checksums = descriptor & 0x80 != 0;
+        let entry_len = if has_checksums { 12 } else { 8 };
+
+        // The seek table is wrapped in a skippable frame
+        let table_len = 8 + u64::from(n_frames) * entry_len + FOOTER_LEN;
+        if len < table_len {
+            return Err(invalid("Seek table is longer than the slice"));
+        }
+        let table = self.read_range((len - table_len)..len)?;
+        let magic = u32::from_le_bytes(table[0..4].try_into().unwrap());
+        let frame_size = u32::from_le_bytes(table[4..8].try_into().unwrap());
+        if magic != SKIPPABLE_MAGIC || u64::from(frame_size) != table_len - 8 {
+            return Err(invalid("Malformed seek table frame header"));
+        }
+
+        let mut frames = Vec::with_capacity(n_frames as usize);
+        let mut offset = 0;
+        let mut decompressed_offset = 0;
+        for entry in table[8..]
+            .chunks_exact(entry_len as usize)
+            .take(n_frames as usize)
+        {
+            let info = FrameInfo {
+                compressed_size: u32::from_le_bytes(entry[0..4].try_into().unwrap()),
+                decompressed_size: u32::from_le_bytes(entry[4..8].try_into().unwrap()),
+                decompressed_offset,
+                checksum: has_checksums
+                    .then(|| u32::from_le_bytes(entry[8..12].try_into().unwrap())),
+            };
+            let end = offset + u64::from(info.compressed_size);
+            frames.push((info, self.slice(offset..end)));
+            offset = end;
+            decompressed_offset += u64::from(info.decompressed_size);
+        }
+        if offset != len - table_len {
+            return Err(invalid("Frame sizes don't match the length of the slice"));
+        }
+        Ok(frames)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::*;
+
+    /// Compress each chunk as a separate frame, and append a seek table
+    fn seekable(chunks: &[&[u8]], checksums: bool) -> Vec<u8> {
+        let mut out = vec![];
+        let mut entries = vec![];
+        for chunk in chunks {
+            let frame = ::zstd::encode_all(*chunk, 3).unwrap();
+            entries.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+            entries.extend_from_slice(&(chunk.len() as u32).to_le_bytes());
+            if checksums {
+                entries.extend_from_slice(&(chunk.len() as u32 ^ 0xdead_beef).to_le_bytes());
+            }
+            out.extend_from_slice(&frame);
+        }
+        out.extend_from_slice(&SKIPPABLE_MAGIC.to_le_bytes());
+        out.extend_from_slice(&(entries.len() as u32 + FOOTER_LEN as u32).to_le_bytes());
+        out.extend_from_slice(&entries);
+        out.extend_from_slice(&(chunks.len() as u32).to_le_bytes());
+        out.push(if checksums { 0x80 } else { 0 });
+        out.extend_from_slice(&SEEKABLE_MAGIC.to_le_bytes());
+        out
+    }
+
+    fn chunks() -> Vec<Vec<u8>> {
+        let data = fixture_bytes(10_000);
+        vec![
+            data[..3000].to_vec(),
+            data[3000..3001].to_vec(),
+            data[3001..].to_vec(),
+        ]
+    }
+
+    fn frames_of(contents: &[u8]) -> std::io::Result<Vec<(FrameInfo, FileSlice)>> {
+        TempFile::new(contents).slice().slice_zstd_frames()
+    }
+
+    #[test]
+    fn decompress_one_frame() {
+        let chunks = chunks();
+        let chunks = chunks.iter().map(|x| &x[..]).collect::<Vec<_>>();
+        let frames = frames_of(&seekable(&chunks, false)).unwrap();
+        assert_eq!(frames.len(), 3);
+        let mut offset = 0;
+        for ((info, _), chunk) in frames.iter().zip(&chunks) {
+            assert_eq!(info.decompressed_size as usize, chunk.len());
+            assert_eq!(info.decompressed_offset, offset);
+            assert_eq!(info.checksum, None);
+            offset += chunk.len() as u64;
+        }
+        let (_, frame) = &frames[2];
+        assert_eq!(::zstd::decode_all(frame.clone()).unwrap(), chunks[2]);
+    }
+
+    #[test]
+    fn with_checksums_in_sub_slice() {
+        let chunks = chunks();
+        let chunks = chunks.iter().map(|x| &x[..]).collect::<Vec<_>>();
+        let table = seekable(&chunks, true);
+        let mut contents = vec![0xff; 100];
+        contents.extend_from_slice(&table);
+        contents.extend_from_slice(&[0xff; 100]);
+        let tmp = TempFile::new(&contents);
+        let slice = tmp.slice().slice(100..100 + table.len() as u64);
+        let frames = slice.slice_zstd_frames().unwrap();
+        assert_eq!(frames[1].0.checksum, Some(1 ^ 0xdead_beef));
+        for ((_, frame), chunk) in frames.into_iter().zip(&chunks) {
+            assert_eq!(::zstd::decode_all(frame).unwrap(), *chunk);
+        }
+    }
+
+    #[test]
+    fn empty_table() {
+        assert!(frames_of(&seekable(&[], false)).unwrap().is_empty());
+    }
+
+    fn assert_invalid(contents: &[u8]) {
+        let err = frames_of(contents).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn malformed_footer() {
+        let good = seekable(&[b"hello", b"world"], false);
+        let n = good.len();
+        // Too short
+        assert_invalid(&good[n - 8..]);
+        // Wrong magic
+        let mut bad = good.clone();
+        bad[n - 1] ^= 1;
+        assert_invalid(&bad);
+        // Reserved bits set
+        let mut bad = good.clone();
+        bad[n - 5] = 0x04;
+        assert_invalid(&bad);
+        // More frames than fit in the file
+        let mut bad = good.clone();
+        bad[n - 9..n - 5].copy_from_slice(&1000u32.to_le_bytes());
+        assert_invalid(&bad);
+        // Skippable frame header mangled
+        let mut bad = good.clone();
+        let header = n - 9 - 2 * 8 - 8;
+        bad[header] ^= 1;
+        assert_invalid(&bad);
+    }
+
+    #[test]
+    fn wrong_frame_size() {
+        let good = seekable(&[b"hello", b"world"], false);
+        let n = good.len();
+        let entry = n - 9 - 2 * 8;
+        let size = u32::from_le_bytes(good[entry..entry + 4].try_into().unwrap());
+        for size in [size - 1, size + 1] {
+            let mut bad = good.clone();
+            bad[entry..entry + 4].copy_from_slice(&size.to_le_bytes());
+            assert_invalid(&bad);
+        }
+        // Extra bytes between the frames and the seek table
+        let mut bad = good[..entry - 8].to_vec();
+        bad.push(0);
+        bad.extend_from_slice(&good[entry - 8..]);
+        assert_invalid(&bad);
+    }
+}